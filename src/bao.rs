@@ -1,9 +1,13 @@
 //! Bao game implementation and related modules.
 pub mod board;
 mod direction;
+pub mod events;
 pub mod game;
 pub mod game_builder;
 pub mod move_factory;
 pub mod moves;
+pub mod notation;
+pub mod perft;
 pub mod player;
 pub mod pv;
+pub mod record;
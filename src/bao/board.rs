@@ -39,6 +39,18 @@ impl Mashumo {
         }
     }
 
+    pub fn from_seeds(seeds: [u8; 16]) -> Mashumo {
+        let mut mashumo = Mashumo {
+            mashumo: [0; 16],
+            bitboard: 0,
+            zobrist: 0,
+        };
+        for (index, value) in seeds.iter().enumerate() {
+            mashumo.set_seeds(index as i8, *value);
+        }
+        mashumo
+    }
+
     pub fn get_and_empty_seeds(&mut self, index: i8) -> u8 {
         let value = self.mashumo[index as usize];
         self.zobrist ^= MASHUMO_RANDS[index as usize].rotate_left(value as u32);
@@ -161,6 +173,13 @@ mod tests {
         assert_eq!(mashumo.mashumo[3], 0);
     }
 
+    #[test]
+    fn test_from_seeds() {
+        let mashumo = Mashumo::from_seeds([0, 0, 0, 0, 6, 2, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(mashumo.mashumo, Mashumo::new().mashumo);
+        assert_eq!(mashumo.bitboard, Mashumo::new().bitboard);
+    }
+
     #[test]
     fn test_get_seeds() {
         let mashumo = Mashumo {
@@ -0,0 +1,286 @@
+//! Events of a single turn.
+//!
+//! A turn is broken down into the steps a player performs on the board: seeds
+//! are picked up, dropped one by one, captured from the opponent and so on.
+//! All pit indices refer to the board of the player to move, except for
+//! captures, which refer to the board of the opponent.
+//!
+//! A `TurnTrace` summarizes the events of a turn as sowing laps and captures
+//! for front-ends that replay turns.
+
+use crate::bao::game::Game;
+use crate::bao::moves::Move;
+use serde::Serialize;
+use std::fmt::{Display, Formatter, Result};
+
+/// A single step of a turn.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum TurnEvent {
+    /// A seed from the hand is placed in a pit.
+    Place { index: i8 },
+    /// Seeds are picked up from a pit to be sown. `nyumba` tells whether the
+    /// pit is a nyumba that still stands.
+    PickUp { index: i8, seeds: u8, nyumba: bool },
+    /// A seed is dropped in a pit.
+    Drop { index: i8 },
+    /// Seeds are captured from a pit of the opponent.
+    Capture { index: i8, seeds: u8 },
+    /// The captured seeds are sown from a kichwa.
+    Kichwa { index: i8 },
+    /// A nyumba is closed, either by a capture or by going on a safari.
+    NyumbaClosed { opponent: bool },
+    /// The turn ended and the players swap.
+    TurnEnded,
+}
+
+impl TurnEvent {
+    /// Returns the pit the event happens at, and whether it is a pit of the opponent.
+    pub fn pit(&self) -> Option<(i8, bool)> {
+        match *self {
+            TurnEvent::Place { index }
+            | TurnEvent::PickUp { index, .. }
+            | TurnEvent::Drop { index }
+            | TurnEvent::Kichwa { index } => Some((index, false)),
+            TurnEvent::Capture { index, .. } => Some((index, true)),
+            TurnEvent::NyumbaClosed { .. } | TurnEvent::TurnEnded => None,
+        }
+    }
+
+    /// Applies the event to a game. Replaying all events of a turn on the
+    /// position before the turn results in the position after the turn.
+    ///
+    /// # Arguments
+    ///
+    /// * `game` - The game to apply the event to.
+    pub fn apply(&self, game: &mut Game) {
+        let (current, other) = (&mut game.current_player, &mut game.other_player);
+        match *self {
+            TurnEvent::Place { index } => {
+                current.mashumo.increment_seeds(index);
+                current.seeds -= 1;
+            }
+            TurnEvent::PickUp { index, seeds, .. } => {
+                let remaining = current.mashumo.get_seeds(index) - seeds;
+                current.mashumo.set_seeds(index, remaining);
+            }
+            TurnEvent::Drop { index } => current.mashumo.increment_seeds(index),
+            TurnEvent::Capture { index, .. } => {
+                other.mashumo.get_and_empty_seeds(index);
+            }
+            TurnEvent::Kichwa { .. } => {}
+            TurnEvent::NyumbaClosed { opponent: true } => other.nyumba = false,
+            TurnEvent::NyumbaClosed { opponent: false } => current.nyumba = false,
+            TurnEvent::TurnEnded => std::mem::swap(current, other),
+        }
+    }
+}
+
+impl Display for TurnEvent {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        match *self {
+            TurnEvent::Place { index } => {
+                write!(f, "Place a seed from the hand in {}", pit_name(index))
+            }
+            TurnEvent::PickUp { index, seeds, .. } => {
+                write!(f, "Pick up {} seeds from {}", seeds, pit_name(index))
+            }
+            TurnEvent::Drop { index } => write!(f, "Drop a seed in {}", pit_name(index)),
+            TurnEvent::Capture { index, seeds } => {
+                write!(f, "Capture {} seeds from {}", seeds, pit_name(index))
+            }
+            TurnEvent::Kichwa { index } => write!(f, "Sow from the kichwa {}", pit_name(index)),
+            TurnEvent::NyumbaClosed { opponent: true } => {
+                write!(f, "The opponent's nyumba is closed")
+            }
+            TurnEvent::NyumbaClosed { opponent: false } => write!(f, "The nyumba is closed"),
+            TurnEvent::TurnEnded => write!(f, "Turn ended"),
+        }
+    }
+}
+
+/// A sowing lap of a turn.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Lap {
+    /// The pit the seeds are picked up from, or the kichwa captured seeds are sown from.
+    pub start: i8,
+    /// Whether the lap sows captured seeds.
+    pub is_capture: bool,
+    /// The number of seeds sown.
+    pub seeds: u8,
+    /// The pits a seed is dropped in, in order. The lap ends in the last pit.
+    pub pits: Vec<i8>,
+}
+
+/// A capture of a turn.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct CaptureTrace {
+    /// The pit of the player to move that captures.
+    pub source: i8,
+    /// The pit of the opponent the seeds are captured from.
+    pub index: i8,
+    /// The number of captured seeds.
+    pub seeds: u8,
+    /// The kichwa the captured seeds are sown from.
+    pub kichwa: i8,
+}
+
+/// The structured record of a turn. All pit indices are as in `TurnEvent`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct TurnTrace {
+    #[serde(rename = "move")]
+    pub m: Move,
+    /// The sowing laps in order.
+    pub laps: Vec<Lap>,
+    /// The captures in order.
+    pub captures: Vec<CaptureTrace>,
+    /// The total number of captured seeds.
+    pub seeds_captured: u8,
+    /// Whether seeds were sown from the standing nyumba of the player to move.
+    pub nyumba_used: bool,
+    /// Whether the player to move lost their nyumba by going on a safari.
+    pub nyumba_lost: bool,
+    /// Whether the nyumba of the opponent was captured.
+    pub opponent_nyumba_lost: bool,
+    /// All events of the turn, to replay it pit by pit.
+    pub events: Vec<TurnEvent>,
+}
+
+impl TurnTrace {
+    /// Creates the trace of a turn from its events.
+    ///
+    /// # Arguments
+    ///
+    /// * `m` - The move that started the turn.
+    /// * `events` - The events of the turn.
+    pub fn new(m: Move, events: Vec<TurnEvent>) -> TurnTrace {
+        let mut trace = TurnTrace {
+            m,
+            laps: vec![],
+            captures: vec![],
+            seeds_captured: 0,
+            nyumba_used: false,
+            nyumba_lost: false,
+            opponent_nyumba_lost: false,
+            events: vec![],
+        };
+        for event in &events {
+            match *event {
+                TurnEvent::PickUp {
+                    index,
+                    seeds,
+                    nyumba,
+                } => {
+                    trace.nyumba_used |= nyumba;
+                    trace.laps.push(Lap {
+                        start: index,
+                        is_capture: false,
+                        seeds,
+                        pits: vec![],
+                    });
+                }
+                TurnEvent::Capture { index, seeds } => {
+                    trace.seeds_captured += seeds;
+                    trace.captures.push(CaptureTrace {
+                        source: 7 - index,
+                        index,
+                        seeds,
+                        kichwa: 0,
+                    });
+                }
+                TurnEvent::Kichwa { index } => {
+                    let seeds = match trace.captures.last_mut() {
+                        Some(capture) => {
+                            capture.kichwa = index;
+                            capture.seeds
+                        }
+                        None => 0,
+                    };
+                    trace.laps.push(Lap {
+                        start: index,
+                        is_capture: true,
+                        seeds,
+                        pits: vec![],
+                    });
+                }
+                TurnEvent::Drop { index } => {
+                    if let Some(lap) = trace.laps.last_mut() {
+                        lap.pits.push(index);
+                    }
+                }
+                TurnEvent::NyumbaClosed { opponent: true } => trace.opponent_nyumba_lost = true,
+                TurnEvent::NyumbaClosed { opponent: false } => trace.nyumba_lost = true,
+                TurnEvent::Place { .. } | TurnEvent::TurnEnded => {}
+            }
+        }
+        trace.events = events;
+        trace
+    }
+
+    /// Writes the trace as JSON.
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+/// Returns the name of a pit as in the move notation, e.g. A5 or B2.
+pub fn pit_name(index: i8) -> String {
+    match index {
+        0..=7 => format!("A{}", index + 1),
+        _ => format!("B{}", index - 7),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::bao::notation;
+    use crate::error::NotationResult;
+
+    #[test]
+    fn test_take_turn_traced() -> NotationResult<()> {
+        let mut game = notation::from_notation(notation::MTAJI_CAPTURE)?;
+        let trace = game.take_turn_traced(&Move::mtaji_capture_right(14));
+        // Sow from B7 to A2, capture from A7 and sow from the left kichwa, then relay from A2.
+        let starts: Vec<(i8, bool, u8)> = trace
+            .laps
+            .iter()
+            .map(|lap| (lap.start, lap.is_capture, lap.seeds))
+            .collect();
+        assert_eq!(starts, [(14, false, 3), (0, true, 2), (1, false, 3)]);
+        assert_eq!(trace.laps[0].pits, [15, 0, 1]);
+        assert_eq!(trace.laps[2].pits, [2, 3, 4]);
+        assert_eq!(
+            trace.captures,
+            [CaptureTrace {
+                source: 1,
+                index: 6,
+                seeds: 2,
+                kichwa: 0
+            }]
+        );
+        assert_eq!(trace.seeds_captured, 2);
+        assert!(!trace.nyumba_used && !trace.nyumba_lost && !trace.opponent_nyumba_lost);
+
+        let json = trace.to_json().expect("Failed to serialize");
+        assert!(json.contains("\"move\": \"B7R\""));
+        assert!(json.contains("\"event\": \"turn_ended\""));
+        Ok(())
+    }
+
+    #[test]
+    fn test_take_turn_traced_nyumba_used() -> NotationResult<()> {
+        // Sow three seeds from A5 to the right, once with and once without the nyumba.
+        let position = "0,0,0,0,3,0,0,0,0,0,0,0,0,0,0,0/1,0,0,0,0,0,0,0,2,0,0,0,0,0,0,0 0/0";
+        let m = Move::mtaji_relay_right(4);
+        let mut game = notation::from_notation(&format!("{} 10", position))?;
+        assert!(game.take_turn_traced(&m).nyumba_used);
+
+        let mut game = notation::from_notation(&format!("{} 00", position))?;
+        let trace = game.take_turn_traced(&m);
+        assert_eq!(trace.laps[0].pits, [5, 6, 7]);
+        assert!(!trace.nyumba_used && !trace.nyumba_lost);
+        Ok(())
+    }
+}
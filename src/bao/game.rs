@@ -1,8 +1,9 @@
 //! Bao game state.
 
 use crate::bao::direction::Direction;
+use crate::bao::events::{TurnEvent, TurnTrace};
 use crate::bao::move_factory::MoveFactory;
-use crate::bao::moves::Move;
+use crate::bao::moves::{flags, Move};
 use crate::bao::player::Player;
 use std::fmt::{Display, Formatter, Result};
 
@@ -26,22 +27,72 @@ impl Game {
         }
     }
 
+    /// Returns whether the game is over. The player to move has lost if it is.
+    pub fn is_over(&self) -> bool {
+        self.current_player.mashumo.bitboard == 0
+            || MoveFactory::new(self).get_legal_moves().is_empty()
+    }
+
     pub fn take_turn(&mut self, first_move: &Move) {
+        self.take_turn_with(first_move, &mut |_| {});
+    }
+
+    /// Takes a turn and records every step of it.
+    ///
+    /// # Arguments
+    ///
+    /// * `first_move` - The move that starts the turn.
+    ///
+    /// # Returns
+    ///
+    /// The events of the turn, ending with `TurnEvent::TurnEnded`.
+    pub fn take_turn_with_events(&mut self, first_move: &Move) -> Vec<TurnEvent> {
+        let mut events = vec![];
+        self.take_turn_with(first_move, &mut |event| events.push(event));
+        events
+    }
+
+    /// Takes a turn and returns its trace.
+    ///
+    /// # Arguments
+    ///
+    /// * `first_move` - The move that starts the turn.
+    ///
+    /// # Returns
+    ///
+    /// The sowing laps, captures and nyumba changes of the turn.
+    pub fn take_turn_traced(&mut self, first_move: &Move) -> TurnTrace {
+        TurnTrace::new(*first_move, self.take_turn_with_events(first_move))
+    }
+
+    fn take_turn_with<F: FnMut(TurnEvent)>(&mut self, first_move: &Move, on_event: &mut F) {
         // Do we have a capture move?
         let is_mtaji_turn = first_move.is_capture();
 
         let mut deq = Some(*first_move);
-        while let Some(m) = deq {
+        while let Some(mut m) = deq {
+            if m.is_capture() && !m.is_namua() && !m.is_relay() {
+                // Mtaji captures sow from their pit first and capture where they land
+                // (A. de Voogt, Limits of the Mind, 1995).
+                m = Move::new(m.index, m.flags & !flags::CAPTURE);
+            }
+
             // Put the move on the board.
             let end_index = if m.is_capture() {
-                self.capture(m.index, m.get_direction(), m.is_namua())
+                self.capture(m.index, m.get_direction(), m.is_namua(), on_event)
             } else {
-                self.relay(m.index, m.get_direction(), m.is_namua(), is_mtaji_turn)
+                self.relay(
+                    m.index,
+                    m.get_direction(),
+                    m.is_namua(),
+                    is_mtaji_turn,
+                    on_event,
+                )
             };
 
-            // Check if the game is over.
+            // An empty front row loses. Still swap, so the loser is the player to move.
             if self.other_player.mashumo.bitboard == 0 {
-                return;
+                break;
             }
 
             // Check follow-up moves.
@@ -52,14 +103,24 @@ impl Game {
 
         // Swap players.
         std::mem::swap(&mut self.current_player, &mut self.other_player);
+        on_event(TurnEvent::TurnEnded);
     }
 
-    fn capture(&mut self, source_index: i8, direction: Direction, is_namua: bool) -> i8 {
+    fn capture<F: FnMut(TurnEvent)>(
+        &mut self,
+        source_index: i8,
+        direction: Direction,
+        is_namua: bool,
+        on_event: &mut F,
+    ) -> i8 {
         // If we're in the NAMUA phase and the move introduces a new seed, add it.
         let capture_index = 7 - source_index;
         if is_namua {
             match self.other_player.mashumo.get_shumo_opposite(capture_index) {
-                Some(index) => self.current_player.mashumo.increment_seeds(index),
+                Some(index) => {
+                    self.current_player.mashumo.increment_seeds(index);
+                    on_event(TurnEvent::Place { index });
+                }
                 None => panic!("Invalid capture index"), // TODO: Improve error handling.
             };
             self.current_player.seeds -= 1;
@@ -67,33 +128,45 @@ impl Game {
 
         // Capture the seeds.
         let seeds = self.other_player.mashumo.get_and_empty_seeds(capture_index);
+        on_event(TurnEvent::Capture {
+            index: capture_index,
+            seeds,
+        });
         if capture_index == 4 {
             // Nyumba capture!
             self.other_player.nyumba = false;
+            on_event(TurnEvent::NyumbaClosed { opponent: true });
         }
-        match direction {
-            Direction::Clockwise => self.current_player.mashumo.sow_cw(0, seeds as usize),
-            Direction::CounterClockwise => self.current_player.mashumo.sow_ccw(7, seeds as usize),
-        }
+        let kichwa = match direction {
+            Direction::Clockwise => 0,
+            Direction::CounterClockwise => 7,
+        };
+        on_event(TurnEvent::Kichwa { index: kichwa });
+        self.sow(kichwa, direction, seeds, on_event)
     }
 
-    fn relay(
+    fn relay<F: FnMut(TurnEvent)>(
         &mut self,
         source_index: i8,
         direction: Direction,
         is_namua: bool,
         is_mtaji_turn: bool,
+        on_event: &mut F,
     ) -> i8 {
         // If we're in the NAMUA phase and the move introduces a new seed, add it.
         if is_namua {
             self.current_player.mashumo.increment_seeds(source_index);
             self.current_player.seeds -= 1;
+            on_event(TurnEvent::Place {
+                index: source_index,
+            });
         }
         let mut seeds = self
             .current_player
             .mashumo
             .get_and_empty_seeds(source_index);
 
+        let nyumba = source_index == 4 && self.current_player.nyumba;
         if source_index == 4 {
             // Special case: Nyumba
             if is_namua {
@@ -106,84 +179,229 @@ impl Game {
             } else if is_mtaji_turn {
                 // Go on a safari!
                 self.current_player.nyumba = false;
+                on_event(TurnEvent::NyumbaClosed { opponent: false });
             }
         }
+        on_event(TurnEvent::PickUp {
+            index: source_index,
+            seeds,
+            nyumba,
+        });
 
-        match direction {
-            Direction::Clockwise => {
-                let (_, start_index) = self.current_player.mashumo.get_shumo_cw(2, source_index);
-                self.current_player
-                    .mashumo
-                    .sow_cw(start_index, seeds as usize)
-            }
+        let (_, start_index) = match direction {
+            Direction::Clockwise => self.current_player.mashumo.get_shumo_cw(2, source_index),
             Direction::CounterClockwise => {
-                let (_, start_index) = self.current_player.mashumo.get_shumo_ccw(2, source_index);
-                self.current_player
-                    .mashumo
-                    .sow_ccw(start_index, seeds as usize)
+                self.current_player.mashumo.get_shumo_ccw(2, source_index)
             }
+        };
+        self.sow(start_index, direction, seeds, on_event)
+    }
+
+    fn sow<F: FnMut(TurnEvent)>(
+        &mut self,
+        start_index: i8,
+        direction: Direction,
+        seeds: u8,
+        on_event: &mut F,
+    ) -> i8 {
+        let mashumo = &mut self.current_player.mashumo;
+        let end_index = match direction {
+            Direction::Clockwise => mashumo.sow_cw(start_index, seeds as usize),
+            Direction::CounterClockwise => mashumo.sow_ccw(start_index, seeds as usize),
+        };
+        // Report the drops after sowing, the board is already up to date.
+        let mut drop = |index: usize| on_event(TurnEvent::Drop { index: index as i8 });
+        match direction {
+            Direction::Clockwise => mashumo
+                .iter_index_cw(seeds as usize, start_index)
+                .for_each(&mut drop),
+            Direction::CounterClockwise => mashumo
+                .iter_index_ccw(seeds as usize, start_index)
+                .for_each(&mut drop),
         }
+        end_index
     }
 }
 
 impl Display for Game {
     fn fmt(&self, f: &mut Formatter) -> Result {
-        let mut lines = "".to_string();
-        // Pretty print the two player boards.
-        lines.push('\n');
-        let other_player_nyumba = if self.other_player.nyumba {
-            "[✓]"
-        } else {
-            "[✗]"
-        };
-        lines.push_str(&format!(
-            "Player 2: seeds={:02}, nyumba={}\n",
-            self.other_player.seeds, other_player_nyumba
-        ));
-        lines.push('\n');
-        lines.push_str("      8 7 6 5 4 3 2 1\n");
-        lines.push_str("    -------------------\n");
-        lines.push_str("    | ");
-        for i in 0..8 {
-            // Other player outer row.
-            lines.push_str(&format!("{} ", self.other_player.mashumo.get_seeds(8 + i)));
-        }
-        lines.push_str("|\n");
-        lines.push_str("  R | ");
-        for i in 0..8 {
-            // Other player inner row.
-            lines.push_str(&format!("{} ", self.other_player.mashumo.get_seeds(7 - i)));
-        }
-        lines.push_str("| L  \n");
-        lines.push_str("  L | ");
-        for i in 0..8 {
-            // Current player inner row.
-            lines.push_str(&format!("{} ", self.current_player.mashumo.get_seeds(i)));
-        }
-        lines.push_str("| R  \n");
-        lines.push_str("    | ");
-        for i in 0..8 {
-            // Current player outer row.
-            lines.push_str(&format!(
-                "{} ",
-                self.current_player.mashumo.get_seeds(15 - i)
-            ));
-        }
-        lines.push_str("|\n");
-        lines.push_str("    -------------------\n");
-        lines.push_str("      1 2 3 4 5 6 7 8\n");
-        lines.push('\n');
-        let current_player_nyumba = if self.current_player.nyumba {
-            "[✓]"
-        } else {
-            "[✗]"
-        };
-        lines.push_str(&format!(
-            "Player 1: seeds={:02}, nyumba={}\n",
-            self.current_player.seeds, current_player_nyumba
-        ));
-        lines.push('\n');
+        let board = format_board(
+            &self.current_player,
+            &self.other_player,
+            "Player 1",
+            "Player 2",
+        );
+        write!(f, "{}", board)
+    }
+}
+
+/// Pretty prints a board with one player at the bottom and the other at the top.
+///
+/// # Arguments
+///
+/// * `bottom` - The player shown at the bottom, whose pits are labelled.
+/// * `top` - The player shown at the top.
+/// * `bottom_name` - The name of the bottom player.
+/// * `top_name` - The name of the top player.
+pub fn format_board(bottom: &Player, top: &Player, bottom_name: &str, top_name: &str) -> String {
+    format_board_with(bottom, top, bottom_name, top_name, |player, index, _| {
+        format!("{} ", player.mashumo.get_seeds(index))
+    })
+}
+
+/// Pretty prints a board like `format_board` with custom pits, e.g. to colour them.
+///
+/// # Arguments
+///
+/// * `bottom` - The player shown at the bottom, whose pits are labelled.
+/// * `top` - The player shown at the top.
+/// * `bottom_name` - The name of the bottom player.
+/// * `top_name` - The name of the top player.
+/// * `pit` - Formats a pit of a player, given whether the player is at the
+///   top, as two columns wide text.
+pub fn format_board_with(
+    bottom: &Player,
+    top: &Player,
+    bottom_name: &str,
+    top_name: &str,
+    pit: impl Fn(&Player, i8, bool) -> String,
+) -> String {
+    let row = |player: &Player, is_top: bool, indices: &mut dyn Iterator<Item = i8>| {
+        indices
+            .map(|index| pit(player, index, is_top))
+            .collect::<String>()
+    };
+    let mut lines = "".to_string();
+    // Pretty print the two player boards.
+    lines.push('\n');
+    lines.push_str(&player_line(top_name, top));
+    lines.push('\n');
+    lines.push_str("      8 7 6 5 4 3 2 1\n");
+    lines.push_str("    -------------------\n");
+    // Top player outer and inner row.
+    lines.push_str(&format!("    | {}|\n", row(top, true, &mut (8..16))));
+    lines.push_str(&format!(
+        "  R | {}| L  \n",
+        row(top, true, &mut (0..8).rev())
+    ));
+    // Bottom player inner and outer row.
+    lines.push_str(&format!("  L | {}| R  \n", row(bottom, false, &mut (0..8))));
+    lines.push_str(&format!(
+        "    | {}|\n",
+        row(bottom, false, &mut (8..16).rev())
+    ));
+    lines.push_str("    -------------------\n");
+    lines.push_str("      1 2 3 4 5 6 7 8\n");
+    lines.push('\n');
+    lines.push_str(&player_line(bottom_name, bottom));
+    lines.push('\n');
+    lines
+}
+
+fn player_line(name: &str, player: &Player) -> String {
+    let nyumba = if player.nyumba { "[✓]" } else { "[✗]" };
+    format!("{}: seeds={:02}, nyumba={}\n", name, player.seeds, nyumba)
+}
+
+#[cfg(test)]
+mod tests {
 
-        write!(f, "{}", lines)
+    use super::*;
+    use crate::bao::notation;
+    use crate::error::NotationResult;
+
+    #[test]
+    fn test_take_turn_mtaji_capture_from_back_row() -> NotationResult<()> {
+        let mut game = notation::from_notation(notation::MTAJI_CAPTURE)?;
+        let m = Move::mtaji_capture_right(14);
+        let mut factory = MoveFactory::new(&game);
+        assert_eq!(factory.get_legal_moves(), &[m]);
+        assert_eq!(factory.get_capture_index(&m), Some(6));
+
+        game.take_turn(&m);
+        // Sow from B7 to A2, capture two seeds from the left kichwa, and relay from A2.
+        assert_eq!(
+            game.other_player.mashumo.mashumo,
+            [2, 0, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]
+        );
+        assert_eq!(
+            game.current_player.mashumo.mashumo,
+            [2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_take_turn_mtaji_capture_sows_before_capturing() -> NotationResult<()> {
+        let mut game = notation::from_notation(
+            "2,0,1,0,0,0,0,0,0,0,0,0,0,0,0,0/0,0,0,0,0,3,0,0,2,0,0,0,0,0,0,0 0/0 00",
+        )?;
+        let m = Move::mtaji_capture_right(0);
+        assert_eq!(MoveFactory::new(&game).get_legal_moves(), &[m]);
+
+        let trace = game.take_turn_traced(&m);
+        // A1 is sown first, its last seed lands in A3 and captures the three seeds opposite.
+        assert_eq!((trace.laps[0].start, trace.laps[0].is_capture), (0, false));
+        assert_eq!(trace.laps[0].pits, [1, 2]);
+        assert_eq!(trace.captures.len(), 1);
+        assert_eq!((trace.captures[0].index, trace.captures[0].seeds), (5, 3));
+        assert_eq!(game.current_player.mashumo.get_seeds(5), 0);
+        assert_eq!(game.other_player.mashumo.get_seeds(0), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_take_turn_winning_capture_swaps_players() -> NotationResult<()> {
+        let mut game = notation::from_notation(
+            "0,1,0,0,0,0,0,0,0,0,0,0,0,0,3,0/0,0,0,0,0,0,2,0,2,0,0,0,0,0,0,0 0/0 00",
+        )?;
+        game.take_turn(&Move::mtaji_capture_right(14));
+        // The player to move has an empty front row and lost the game.
+        assert!(game.is_over());
+        assert_eq!(game.current_player.mashumo.bitboard, 0);
+        assert_ne!(game.other_player.mashumo.bitboard, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_take_turn_winning_namua_capture_ends_game() -> NotationResult<()> {
+        let mut game = notation::from_notation(
+            "0,0,1,0,0,0,0,0,0,0,0,0,0,0,0,0/0,0,0,0,0,2,0,0,3,0,0,0,0,0,0,0 1/0 00",
+        )?;
+        assert!(!game.is_over());
+        let events = game.take_turn_with_events(&Move::namua_capture_right(2));
+        // Capturing the last front row seeds ends the game with the loser to move.
+        assert_eq!(events.last(), Some(&TurnEvent::TurnEnded));
+        assert!(game.is_over());
+        assert_eq!(game.current_player.mashumo.get_seeds(8), 3);
+        assert_eq!(game.other_player.seeds, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_take_turn_with_events_replays_turn() {
+        let mut game = Game::new();
+        for ply in 0..60 {
+            let moves = MoveFactory::new(&game).get_legal_moves().to_vec();
+            let Some(m) = moves.get(ply % moves.len().max(1)) else {
+                break;
+            };
+            let mut replay = game.clone();
+            let events = game.clone().take_turn_with_events(m);
+            assert_eq!(events.last(), Some(&TurnEvent::TurnEnded));
+            for event in &events {
+                event.apply(&mut replay);
+            }
+            game.take_turn(m);
+            for (actual, expected) in [
+                (&replay.current_player, &game.current_player),
+                (&replay.other_player, &game.other_player),
+            ] {
+                assert_eq!(actual.mashumo.mashumo, expected.mashumo.mashumo);
+                assert_eq!(actual.mashumo.zobrist, expected.mashumo.zobrist);
+                assert_eq!(actual.seeds, expected.seeds);
+                assert_eq!(actual.nyumba, expected.nyumba);
+            }
+        }
     }
 }
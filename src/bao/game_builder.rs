@@ -1,30 +1,59 @@
 use crate::bao::game::Game;
-use crate::bao::move_factory::MoveFactory;
+use crate::bao::move_factory::{self, MoveFactory};
 use crate::bao::moves::Move;
+use crate::bao::notation;
 use crate::error::{GameBuilderError, GameBuilderResult};
-use regex::Regex;
 
 #[derive(Debug, Default)]
 pub struct GameBuilder {
     game: Game,
+    moves: Vec<Move>,
 }
 
 impl GameBuilder {
     pub fn new() -> GameBuilder {
-        GameBuilder { game: Game::new() }
+        GameBuilder {
+            game: Game::new(),
+            moves: vec![],
+        }
+    }
+
+    pub fn from_notation(position: &str) -> GameBuilderResult<GameBuilder> {
+        Ok(GameBuilder {
+            game: notation::from_notation(position)?,
+            moves: vec![],
+        })
+    }
+
+    /// Creates a builder that continues from a game.
+    pub fn from_game(game: Game) -> GameBuilder {
+        GameBuilder {
+            game,
+            moves: vec![],
+        }
     }
 
     pub fn build(self) -> Game {
         self.game
     }
 
+    /// Returns the game built so far.
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// Returns the moves played so far.
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
     pub fn with_move(mut self, m: Move) -> GameBuilderResult<GameBuilder> {
         let mut move_factory = MoveFactory::new(&self.game);
-        let legal_moves = move_factory.get_legal_moves();
-        if !legal_moves.contains(&m) {
-            return Err(GameBuilderError::IllegalMove(format!("{}", m)));
+        if let Some(reason) = move_factory.explain_illegal_move(&m) {
+            return Err(GameBuilderError::IllegalMove(format!("{} ({})", m, reason)));
         }
         self.game.take_turn(&m);
+        self.moves.push(m);
         Ok(self)
     }
 
@@ -36,16 +65,27 @@ impl GameBuilder {
     }
 
     pub fn with_move_str(self, m: &str) -> GameBuilderResult<GameBuilder> {
-        println!("{}", m);
         let move_factory = MoveFactory::new(&self.game);
         let m = move_factory.parse_move(m)?;
         self.with_move(m)
     }
 
+    /// Plays moves such as "6L 5R; 3L". Moves are separated by whitespace or
+    /// semicolons and every other word is an error naming it.
     pub fn with_moves_str(mut self, moves: &str) -> GameBuilderResult<GameBuilder> {
-        let re = Regex::new(r"\d+[LR]|[aAbB]\d+[LR]|\b\d\b").unwrap();
-        for cap in re.captures_iter(moves) {
-            self = self.with_move_str(&cap[0])?;
+        let tokens = moves
+            .split(|c: char| c.is_whitespace() || c == ';')
+            .filter(|token| !token.is_empty());
+        for token in tokens {
+            let invalid =
+                |reason: String| GameBuilderError::InvalidToken(token.to_string(), reason);
+            if self.game.is_over() {
+                return Err(invalid("the game is over".to_string()));
+            }
+            let m = MoveFactory::new(&self.game)
+                .parse_move(token)
+                .map_err(|e| invalid(move_factory::parse_error_message(token, &e)))?;
+            self = self.with_move(m).map_err(|e| invalid(e.to_string()))?;
         }
         Ok(self)
     }
@@ -124,4 +164,27 @@ mod tests {
         assert_eq!(game.other_player.seeds, 16);
         Ok(())
     }
+
+    #[test]
+    fn test_game_builder_with_moves_str_rejects_bad_tokens() {
+        for (moves, token) in [("6L 5R; 3l", "3l"), ("6L, 5R", "6L,"), ("6L 9L", "9L")] {
+            match GameBuilder::default().with_moves_str(moves) {
+                Err(GameBuilderError::InvalidToken(t, _)) => assert_eq!(t, token),
+                other => panic!("{:?}", other.map(|b| b.moves().to_vec())),
+            }
+        }
+    }
+
+    #[test]
+    fn test_game_builder_with_mtaji_moves_str() -> GameBuilderResult<()> {
+        let builder = GameBuilder::from_notation(
+            "0,1,0,0,0,0,0,0,0,0,0,0,0,0,3,0/2,0,0,0,0,0,2,0,2,0,0,0,0,0,0,0 0/0 00",
+        )?
+        .with_moves_str("B7R; A1R")?;
+        assert_eq!(
+            builder.moves(),
+            &[Move::mtaji_capture_right(14), Move::mtaji_relay_right(0)]
+        );
+        Ok(())
+    }
 }
@@ -4,6 +4,58 @@ use crate::bao::direction::Direction;
 use crate::bao::game::Game;
 use crate::bao::moves::{flags, Move};
 use crate::error::{MoveFactoryError, MoveFactoryResult};
+use std::fmt::{Display, Formatter};
+
+/// The reason why a move is illegal.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IllegalMoveReason {
+    /// The player still has seeds in hand but the move is a mtaji move.
+    NamuaStage,
+    /// The player has no seeds in hand but the move is a namua move.
+    MtajiStage,
+    /// The pit is empty.
+    EmptyPit,
+    /// Mtaji moves need at least two seeds in the pit.
+    SingleSeed,
+    /// A capture is available, so a non-capturing move is illegal.
+    CaptureAvailable,
+    /// The capture does not take any seeds.
+    NothingToCapture,
+    /// Captures from a kimbi sow from the nearest end of the row.
+    KimbiDirection,
+    /// The nyumba may only be relay-sown in the namua stage if it is the only
+    /// non-empty pit.
+    NyumbaRelay,
+    /// The move is not legal for another reason.
+    NotLegal,
+}
+
+impl Display for IllegalMoveReason {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let reason = match self {
+            IllegalMoveReason::NamuaStage => {
+                "you still have seeds in hand, so you must play a namua move from the front row, e.g. 6L"
+            }
+            IllegalMoveReason::MtajiStage => {
+                "you have no seeds in hand, so you must give a row, pit and direction, e.g. A5R"
+            }
+            IllegalMoveReason::EmptyPit => "the pit is empty",
+            IllegalMoveReason::SingleSeed => {
+                "the pit holds a single seed, but you need at least two to sow in the mtaji stage"
+            }
+            IllegalMoveReason::CaptureAvailable => "a capture is available, so you must capture",
+            IllegalMoveReason::NothingToCapture => "this move does not capture anything",
+            IllegalMoveReason::KimbiDirection => {
+                "kimbi captures have a fixed direction, enter the pit number alone, e.g. 1 or 8"
+            }
+            IllegalMoveReason::NyumbaRelay => {
+                "you cannot relay-sow from the nyumba in the namua stage while other pits hold seeds"
+            }
+            IllegalMoveReason::NotLegal => "the move is not legal in this position",
+        };
+        write!(f, "{}", reason)
+    }
+}
 
 pub struct MoveFactory<'a> {
     game: &'a Game,
@@ -34,6 +86,13 @@ impl<'a> MoveFactory<'a> {
     /// TODO
     // Note: This does not check if a move is legal.
     pub fn parse_move(&self, s: &str) -> MoveFactoryResult<Move> {
+        // Moves are plain ASCII, which also keeps the byte slicing below on char boundaries.
+        if !s.is_ascii() {
+            return Err(MoveFactoryError::ParseInvalidCharacters(s.to_string()));
+        }
+        if s.starts_with(['A', 'a', 'B', 'b']) {
+            return self.parse_mtaji_move(s);
+        }
         match s.len() {
             1 => {
                 // Always a capture and always namua. Get index and direction.
@@ -66,15 +125,105 @@ impl<'a> MoveFactory<'a> {
                     }
                 }
             }
-            3 => {
-                // Either a capture or a non-capture. Never namua.
-                let _is_capture = false; // TODO: Implement capture detection.
-                panic!("Not implemented");
-            }
             _ => Err(MoveFactoryError::ParseInvalidLength(s.len())),
         }
     }
 
+    /// Parses a mtaji move with a row, a pit and a direction, e.g. "A5R" or "B2L".
+    fn parse_mtaji_move(&self, s: &str) -> MoveFactoryResult<Move> {
+        if !(2..=3).contains(&s.len()) {
+            return Err(MoveFactoryError::ParseInvalidLength(s.len()));
+        }
+        let pit = s[1..2].parse::<i8>()?;
+        if !(1..=8).contains(&pit) {
+            return Err(MoveFactoryError::ParseInvalidIndex(pit));
+        }
+        let index = match &s[0..1] {
+            "A" | "a" => pit - 1,
+            _ => pit + 7,
+        };
+        let is_right = match s.get(2..) {
+            Some("R") => Some(true),
+            Some("L") => Some(false),
+            Some("") => None,
+            Some(direction) => {
+                return Err(MoveFactoryError::ParseInvalidDirection(
+                    direction.to_string(),
+                ))
+            }
+            None => return Err(MoveFactoryError::ParseInvalidLength(s.len())),
+        };
+
+        // Whether the move captures depends on the position, so look it up.
+        let mut factory = MoveFactory::new(self.game);
+        let legal_move = factory.get_legal_moves().iter().copied().find(|m| {
+            m.index == index
+                && is_right.is_none_or(|is_right| (m.flags & flags::DIRECTION_R != 0) == is_right)
+        });
+        match (legal_move, is_right) {
+            (Some(m), _) => Ok(m),
+            (None, Some(true)) => Ok(Move::mtaji_relay_right(index)),
+            (None, Some(false)) => Ok(Move::mtaji_relay_left(index)),
+            (None, None) => Err(MoveFactoryError::ParseInvalidDirection("".to_string())),
+        }
+    }
+
+    /// Explains why a move is illegal.
+    ///
+    /// # Arguments
+    ///
+    /// * `m` - The move to check.
+    ///
+    /// # Returns
+    ///
+    /// The reason why the move is illegal, or `None` if it is legal.
+    pub fn explain_illegal_move(&mut self, m: &Move) -> Option<IllegalMoveReason> {
+        let legal_moves = self.get_legal_moves();
+        if legal_moves.contains(m) {
+            return None;
+        }
+        let capture_available = legal_moves.iter().any(|m| m.is_capture());
+        let current = &self.game.current_player;
+        let seeds = match m.index {
+            0..=15 => current.mashumo.get_seeds(m.index),
+            _ => return Some(IllegalMoveReason::NotLegal),
+        };
+
+        let reason = if current.seeds > 0 {
+            // Namua stage.
+            let is_only_nyumba = current.mashumo.bitboard.count_ones() == 1;
+            let opposite = self.game.other_player.mashumo.get_seeds(7 - m.index);
+            if !m.is_namua() || m.index > 7 {
+                IllegalMoveReason::NamuaStage
+            } else if seeds == 0 {
+                IllegalMoveReason::EmptyPit
+            } else if m.is_capture() && opposite == 0 {
+                IllegalMoveReason::NothingToCapture
+            } else if m.is_capture() && matches!(m.index, 0..=1 | 6..=7) {
+                IllegalMoveReason::KimbiDirection
+            } else if !m.is_capture() && capture_available {
+                IllegalMoveReason::CaptureAvailable
+            } else if m.index == 4 && !is_only_nyumba {
+                IllegalMoveReason::NyumbaRelay
+            } else {
+                IllegalMoveReason::NotLegal
+            }
+        } else if m.is_namua() {
+            IllegalMoveReason::MtajiStage
+        } else if seeds == 0 {
+            IllegalMoveReason::EmptyPit
+        } else if seeds == 1 {
+            IllegalMoveReason::SingleSeed
+        } else if m.is_capture() {
+            IllegalMoveReason::NothingToCapture
+        } else if capture_available {
+            IllegalMoveReason::CaptureAvailable
+        } else {
+            IllegalMoveReason::NotLegal
+        };
+        Some(reason)
+    }
+
     /// TODO
     pub fn get_follow_up_move_at_index(
         &self,
@@ -123,6 +272,29 @@ impl<'a> MoveFactory<'a> {
         })
     }
 
+    /// Returns the index of the opponent's pit that a capture move takes seeds from.
+    ///
+    /// Returns `None` if the move is not a capture.
+    pub fn get_capture_index(&self, m: &Move) -> Option<i8> {
+        if !m.is_capture() {
+            return None;
+        }
+        let mashumo = &self.game.current_player.mashumo;
+        if m.is_namua() || m.is_relay() {
+            // The move captures directly from the pit opposite of its index.
+            return mashumo.get_shumo_opposite(m.index);
+        }
+
+        // Mtaji captures first sow from the source pit and capture where they land.
+        let seeds = mashumo.get_seeds(m.index) as usize;
+        let (_, landing_index) = if m.flags & flags::DIRECTION_R != 0 {
+            mashumo.get_shumo_cw(seeds + 1, m.index)
+        } else {
+            mashumo.get_shumo_ccw(seeds + 1, m.index)
+        };
+        mashumo.get_shumo_opposite(landing_index)
+    }
+
     fn get_legal_moves_mtaji(&mut self) -> &[Move] {
         let captures = self.game.current_player.mashumo.bitboard
             & self.game.other_player.mashumo.bitboard.reverse_bits();
@@ -222,10 +394,38 @@ impl<'a> MoveFactory<'a> {
     }
 }
 
+/// Returns a message for a player who entered a move that cannot be parsed.
+///
+/// # Arguments
+///
+/// * `input` - The entered move.
+/// * `error` - The error of `MoveFactory::parse_move`.
+pub fn parse_error_message(input: &str, error: &MoveFactoryError) -> String {
+    let format = "Namua moves are a pit and a direction, e.g. 6L, or a kimbi alone, e.g. 1. \
+                  Mtaji moves are a row, a pit and a direction, e.g. A5R or B2L.";
+    match error {
+        MoveFactoryError::ParseInvalidIndex(index) => {
+            format!("There is no pit {}, the pits are numbered 1 to 8.", index)
+        }
+        MoveFactoryError::ParseInvalidDirection(direction) if direction.is_empty() => {
+            format!("{} needs a direction, L or R.", input)
+        }
+        MoveFactoryError::ParseInvalidDirection(direction) => {
+            format!("{} is not a direction, use L or R.", direction)
+        }
+        MoveFactoryError::ParseError(_)
+        | MoveFactoryError::ParseInvalidLength(_)
+        | MoveFactoryError::ParseInvalidCharacters(_) => {
+            format!("{} is not a move. {}", input, format)
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
     use super::*;
+    use crate::bao::notation;
 
     #[test]
     fn test_parse_starting_position() -> MoveFactoryResult<()> {
@@ -295,6 +495,114 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_parse_mtaji_moves() -> MoveFactoryResult<()> {
+        let game = notation::from_notation(notation::MTAJI_CAPTURE).expect("Invalid position");
+        let factory = MoveFactory::new(&game);
+        // Captures are looked up in the legal moves.
+        assert_eq!(factory.parse_move("B7R")?, Move::mtaji_capture_right(14));
+        assert_eq!(factory.parse_move("b7")?, Move::mtaji_capture_right(14));
+        // Other moves are parsed as relays.
+        assert_eq!(factory.parse_move("B7L")?, Move::mtaji_relay_left(14));
+        assert_eq!(factory.parse_move("A2R")?, Move::mtaji_relay_right(1));
+        assert!(factory.parse_move("A2").is_err());
+        assert!(factory.parse_move("A9R").is_err());
+        assert!(factory.parse_move("A1X").is_err());
+        assert!(factory.parse_move("A1RR").is_err());
+        assert!(matches!(
+            factory.parse_move("Aé"),
+            Err(MoveFactoryError::ParseInvalidCharacters(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_explain_illegal_namua_moves() -> MoveFactoryResult<()> {
+        let game = Game::new();
+        let mut factory = MoveFactory::new(&game);
+        let explain = |factory: &mut MoveFactory, s: &str| -> MoveFactoryResult<_> {
+            let m = factory.parse_move(s)?;
+            Ok(factory.explain_illegal_move(&m))
+        };
+        assert_eq!(explain(&mut factory, "6L")?, None);
+        assert_eq!(
+            explain(&mut factory, "3L")?,
+            Some(IllegalMoveReason::EmptyPit)
+        );
+        assert_eq!(
+            explain(&mut factory, "5L")?,
+            Some(IllegalMoveReason::NyumbaRelay)
+        );
+        assert_eq!(
+            factory.explain_illegal_move(&Move::mtaji_relay_left(4)),
+            Some(IllegalMoveReason::NamuaStage)
+        );
+
+        let mut game = Game::new();
+        game.take_turn(&factory.parse_move("6L")?);
+        let mut factory = MoveFactory::new(&game);
+        assert_eq!(
+            explain(&mut factory, "7L")?,
+            Some(IllegalMoveReason::CaptureAvailable)
+        );
+
+        let game = notation::from_notation(
+            "0,1,0,0,6,2,2,0,0,0,0,0,0,0,0,0/0,0,0,0,6,2,2,0,0,0,0,0,0,0,0,0 21/22 11",
+        )
+        .expect("Invalid position");
+        let mut factory = MoveFactory::new(&game);
+        assert_eq!(explain(&mut factory, "2")?, None);
+        assert_eq!(
+            explain(&mut factory, "2R")?,
+            Some(IllegalMoveReason::KimbiDirection)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_explain_illegal_mtaji_moves() -> MoveFactoryResult<()> {
+        let game = notation::from_notation(notation::MTAJI_CAPTURE).expect("Invalid position");
+        let mut factory = MoveFactory::new(&game);
+        let explain = |factory: &mut MoveFactory, s: &str| -> MoveFactoryResult<_> {
+            let m = factory.parse_move(s)?;
+            Ok(factory.explain_illegal_move(&m))
+        };
+        assert_eq!(explain(&mut factory, "B7R")?, None);
+        assert_eq!(
+            explain(&mut factory, "B7L")?,
+            Some(IllegalMoveReason::CaptureAvailable)
+        );
+        assert_eq!(
+            explain(&mut factory, "A2R")?,
+            Some(IllegalMoveReason::SingleSeed)
+        );
+        assert_eq!(
+            explain(&mut factory, "A3R")?,
+            Some(IllegalMoveReason::EmptyPit)
+        );
+        assert_eq!(
+            factory.explain_illegal_move(&Move::namua_relay_left(1)),
+            Some(IllegalMoveReason::MtajiStage)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_error_message() {
+        let game = Game::new();
+        let factory = MoveFactory::new(&game);
+        let message = |s: &str| match factory.parse_move(s) {
+            Err(e) => parse_error_message(s, &e),
+            Ok(_) => panic!(),
+        };
+        assert_eq!(
+            message("9L"),
+            "There is no pit 9, the pits are numbered 1 to 8."
+        );
+        assert_eq!(message("5X"), "X is not a direction, use L or R.");
+        assert!(message("hello").starts_with("hello is not a move."));
+    }
+
     #[test]
     fn test_parse_errors() {
         let game = Game::new();
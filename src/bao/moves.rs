@@ -1,6 +1,7 @@
 //! Bao moves.
 
 use crate::bao::direction::Direction;
+use serde::{Serialize, Serializer};
 use std::fmt::{Display, Formatter, Result};
 
 pub mod flags {
@@ -108,6 +109,10 @@ impl Move {
     pub fn is_capture(&self) -> bool {
         self.flags & flags::CAPTURE != 0
     }
+
+    pub fn is_relay(&self) -> bool {
+        self.flags & flags::RELAY != 0
+    }
 }
 
 impl Display for Move {
@@ -132,8 +137,9 @@ impl Display for Move {
                     "R"
                 }
             }
-            (0..=1, _, _) => "",
-            (6..=7, _, _) => "",
+            // Namua captures from a kimbi have a fixed direction.
+            (0..=1, _, _) if self.is_namua() => "",
+            (6..=7, _, _) if self.is_namua() => "",
             (_, direction, _) => {
                 // Capture.
                 if direction == 0 {
@@ -154,6 +160,13 @@ impl Display for Move {
     }
 }
 
+impl Serialize for Move {
+    /// Serializes the move in notation.
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -184,6 +197,19 @@ mod tests {
         assert_eq!(right_move.get_direction(), Direction::Clockwise);
     }
 
+    #[test]
+    fn test_display() {
+        assert_eq!(Move::namua_relay_left(5).to_string(), "6L");
+        assert_eq!(Move::namua_capture_right(2).to_string(), "3R");
+        assert_eq!(Move::namua_capture_left(0).to_string(), "1");
+        assert_eq!(Move::namua_capture_right(7).to_string(), "8");
+        assert_eq!(Move::mtaji_relay_right(4).to_string(), "A5R");
+        assert_eq!(Move::mtaji_capture_left(9).to_string(), "B2L");
+        // Mtaji captures from a kimbi can go in both directions.
+        assert_eq!(Move::mtaji_capture_left(0).to_string(), "A1L");
+        assert_eq!(Move::mtaji_capture_right(0).to_string(), "A1R");
+    }
+
     #[test]
     fn test_is_namua() {
         let move1 = Move {
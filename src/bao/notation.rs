@@ -0,0 +1,156 @@
+//! Position notation.
+//!
+//! A position is written as three fields separated by spaces:
+//!
+//! 1. The 16 pits of the player to move and the 16 pits of the other player,
+//!    separated by `/`. The pits of a player are listed by index, i.e. the
+//!    front row from left to right followed by the back row from right to left.
+//! 2. The seeds in hand of the player to move and of the other player.
+//! 3. Whether the player to move and the other player still own their nyumba.
+//!
+//! The starting position is written as
+//! `0,0,0,0,6,2,2,0,0,0,0,0,0,0,0,0/0,0,0,0,6,2,2,0,0,0,0,0,0,0,0,0 22/22 11`.
+
+use crate::bao::board::Mashumo;
+use crate::bao::game::Game;
+use crate::bao::player::Player;
+use crate::error::{NotationError, NotationResult};
+
+/// A mtaji position shared by the tests of turns. B7R is the only legal move:
+/// it sows from B7 to A2, captures two seeds from A7 and relays from A2.
+#[cfg(test)]
+pub(crate) const MTAJI_CAPTURE: &str =
+    "0,1,0,0,0,0,0,0,0,0,0,0,0,0,3,0/2,0,0,0,0,0,2,0,0,0,0,0,0,0,0,0 0/0 00";
+
+/// Writes the position of a game in notation.
+pub fn to_notation(game: &Game) -> String {
+    format!(
+        "{}/{} {}/{} {}{}",
+        pits_to_notation(&game.current_player),
+        pits_to_notation(&game.other_player),
+        game.current_player.seeds,
+        game.other_player.seeds,
+        game.current_player.nyumba as u8,
+        game.other_player.nyumba as u8,
+    )
+}
+
+/// Parses a position written in notation.
+pub fn from_notation(s: &str) -> NotationResult<Game> {
+    let fields: Vec<&str> = s.split_whitespace().collect();
+    if fields.len() != 3 {
+        return Err(NotationError::InvalidFieldCount(fields.len()));
+    }
+
+    let (current_pits, other_pits) = split_pair(fields[0])?;
+    let (current_seeds, other_seeds) = split_pair(fields[1])?;
+    let nyumba: Vec<char> = fields[2].chars().collect();
+    if nyumba.len() != 2 || nyumba.iter().any(|c| *c != '0' && *c != '1') {
+        return Err(NotationError::InvalidNyumba(fields[2].to_string()));
+    }
+
+    Ok(Game {
+        current_player: Player {
+            mashumo: Mashumo::from_seeds(pits_from_notation(current_pits)?),
+            seeds: current_seeds.parse()?,
+            nyumba: nyumba[0] == '1',
+        },
+        other_player: Player {
+            mashumo: Mashumo::from_seeds(pits_from_notation(other_pits)?),
+            seeds: other_seeds.parse()?,
+            nyumba: nyumba[1] == '1',
+        },
+    })
+}
+
+fn split_pair(field: &str) -> NotationResult<(&str, &str)> {
+    field
+        .split_once('/')
+        .ok_or_else(|| NotationError::InvalidField(field.to_string()))
+}
+
+fn pits_to_notation(player: &Player) -> String {
+    let pits: Vec<String> = player
+        .mashumo
+        .mashumo
+        .iter()
+        .map(|seeds| seeds.to_string())
+        .collect();
+    pits.join(",")
+}
+
+fn pits_from_notation(s: &str) -> NotationResult<[u8; 16]> {
+    let values = s
+        .split(',')
+        .map(|v| v.parse::<u8>())
+        .collect::<Result<Vec<u8>, _>>()?;
+    values
+        .try_into()
+        .map_err(|values: Vec<u8>| NotationError::InvalidPitCount(values.len()))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::bao::game_builder::GameBuilder;
+
+    #[test]
+    fn test_to_notation_starting_position() {
+        let game = Game::new();
+        assert_eq!(
+            to_notation(&game),
+            "0,0,0,0,6,2,2,0,0,0,0,0,0,0,0,0/0,0,0,0,6,2,2,0,0,0,0,0,0,0,0,0 22/22 11"
+        );
+    }
+
+    #[test]
+    fn test_notation_roundtrip() -> NotationResult<()> {
+        let game = crate::bao_game!("7L", "5R");
+        let parsed = from_notation(&to_notation(&game))?;
+        assert_eq!(
+            parsed.current_player.mashumo.mashumo,
+            game.current_player.mashumo.mashumo
+        );
+        assert_eq!(
+            parsed.other_player.mashumo.mashumo,
+            game.other_player.mashumo.mashumo
+        );
+        assert_eq!(
+            parsed.current_player.mashumo.bitboard,
+            game.current_player.mashumo.bitboard
+        );
+        assert_eq!(parsed.current_player.seeds, 21);
+        assert_eq!(parsed.other_player.seeds, 21);
+        assert_eq!(to_notation(&parsed), to_notation(&game));
+        Ok(())
+    }
+
+    #[test]
+    fn test_notation_errors() {
+        match from_notation("0,0/0,0 22/22") {
+            Err(e) => assert_eq!(e.to_string(), "InvalidFieldCount: 2"),
+            _ => panic!(),
+        }
+        match from_notation("0,0/0,0 22/22 11") {
+            Err(e) => assert_eq!(e.to_string(), "InvalidPitCount: 2"),
+            _ => panic!(),
+        }
+        match from_notation("0,0,0,0,6,2,2,0,0,0,0,0,0,0,0,0 22/22 11") {
+            Err(e) => assert_eq!(
+                e.to_string(),
+                "InvalidField: 0,0,0,0,6,2,2,0,0,0,0,0,0,0,0,0"
+            ),
+            _ => panic!(),
+        }
+        let pits = "0,0,0,0,6,2,2,0,0,0,0,0,0,0,0,0/0,0,0,0,6,2,2,0,0,0,0,0,0,0,0,0";
+        match from_notation(&format!("{} 22/22 12", pits)) {
+            Err(e) => assert_eq!(e.to_string(), "InvalidNyumba: 12"),
+            _ => panic!(),
+        }
+        match from_notation(&format!("{} 22/x 11", pits)) {
+            Err(e) => assert_eq!(e.to_string(), "ParseError: invalid digit found in string"),
+            _ => panic!(),
+        }
+    }
+}
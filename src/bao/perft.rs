@@ -0,0 +1,83 @@
+//! Move generation counts.
+//!
+//! Perft counts the positions reachable in a fixed number of turns. The counts
+//! of a position never change unless the rules or the move generation do, so
+//! they make a good regression check.
+
+use crate::bao::game::Game;
+use crate::bao::move_factory::MoveFactory;
+use crate::bao::moves::Move;
+
+/// Counts the positions reachable in exactly `depth` turns. Games that end
+/// earlier are not counted.
+pub fn perft(game: &Game, depth: u8) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let moves = legal_moves(game);
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+    moves
+        .iter()
+        .map(|m| {
+            let mut next = game.clone();
+            next.take_turn(m);
+            perft(&next, depth - 1)
+        })
+        .sum()
+}
+
+/// Counts the positions reachable in exactly `depth` turns after each legal move.
+pub fn divide(game: &Game, depth: u8) -> Vec<(Move, u64)> {
+    legal_moves(game)
+        .into_iter()
+        .map(|m| {
+            let mut next = game.clone();
+            next.take_turn(&m);
+            (m, perft(&next, depth.saturating_sub(1)))
+        })
+        .collect()
+}
+
+/// Returns the legal moves, or none if the player to move has lost.
+fn legal_moves(game: &Game) -> Vec<Move> {
+    match game.current_player.mashumo.bitboard {
+        0 => vec![],
+        _ => MoveFactory::new(game).get_legal_moves().to_vec(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::bao::notation;
+    use crate::error::NotationResult;
+
+    #[test]
+    fn test_perft_starting_position() {
+        let game = Game::new();
+        let counts: Vec<u64> = (0..=7).map(|depth| perft(&game, depth)).collect();
+        assert_eq!(counts, [1, 4, 14, 38, 122, 352, 1191, 4649]);
+        let divided: u64 = divide(&game, 3).iter().map(|(_, count)| count).sum();
+        assert_eq!(divided, counts[3]);
+    }
+
+    #[test]
+    fn test_perft_mtaji_positions() -> NotationResult<()> {
+        // Mtaji captures from the back row, which only work by sowing first.
+        let game = notation::from_notation(
+            "0,1,0,0,0,0,0,0,0,0,0,0,0,0,3,0/2,0,0,0,0,0,2,0,2,0,0,0,0,0,0,0 0/0 00",
+        )?;
+        let counts: Vec<u64> = (0..=5).map(|depth| perft(&game, depth)).collect();
+        assert_eq!(counts, [1, 1, 4, 8, 12, 27]);
+        // Every line ends within five plies, after which the loser has no moves.
+        let game = notation::from_notation(
+            "0,0,1,0,0,0,0,0,0,2,1,0,2,1,0,1/0,4,2,5,7,1,4,2,2,3,1,3,13,2,5,2 0/0 00",
+        )?;
+        let counts: Vec<u64> = (0..=5).map(|depth| perft(&game, depth)).collect();
+        assert_eq!(counts, [1, 4, 8, 4, 7, 0]);
+        Ok(())
+    }
+}
@@ -1,10 +1,11 @@
 //! Principal variation lines.
 
 use crate::bao::moves::Move;
+use serde::Serialize;
 use std::cmp::{Ordering, PartialEq, PartialOrd};
 use std::fmt::{Display, Formatter, Result};
 
-#[derive(Clone, PartialEq)]
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
 pub struct PVLine {
     pub moves: Vec<Move>,
     pub value: f32,
@@ -0,0 +1,298 @@
+//! Game records.
+//!
+//! A record starts with tag lines of the form `[Key "Value"]`, followed by the
+//! moves in the same notation as the `--moves` argument of the command line:
+//!
+//! ```text
+//! [Event "kikande match"]
+//! [First "depth=6"]
+//! [Second "depth=4"]
+//! [Result "1-0"]
+//!
+//! 6L 5R; 3L 5L; 8 8;
+//! ```
+//!
+//! The optional `Position` tag holds the starting position in notation. A file
+//! can hold several records, each starting with its tags.
+
+use crate::bao::game::Game;
+use crate::bao::game_builder::GameBuilder;
+use crate::bao::moves::Move;
+use crate::error::{RecordError, RecordResult};
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+/// The result of a game.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum GameResult {
+    /// The player who moved first won.
+    FirstPlayerWins,
+    /// The player who moved second won.
+    SecondPlayerWins,
+    Draw,
+    /// The game is still in progress or was abandoned.
+    #[default]
+    Unknown,
+}
+
+impl Display for GameResult {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            GameResult::FirstPlayerWins => write!(f, "1-0"),
+            GameResult::SecondPlayerWins => write!(f, "0-1"),
+            GameResult::Draw => write!(f, "1/2-1/2"),
+            GameResult::Unknown => write!(f, "*"),
+        }
+    }
+}
+
+impl FromStr for GameResult {
+    type Err = RecordError;
+
+    fn from_str(s: &str) -> RecordResult<GameResult> {
+        match s {
+            "1-0" => Ok(GameResult::FirstPlayerWins),
+            "0-1" => Ok(GameResult::SecondPlayerWins),
+            "1/2-1/2" => Ok(GameResult::Draw),
+            "*" => Ok(GameResult::Unknown),
+            _ => Err(RecordError::InvalidResult(s.to_string())),
+        }
+    }
+}
+
+/// A recorded game.
+#[derive(Clone, Debug, Default)]
+pub struct GameRecord {
+    /// Tags other than `Position` and `Result`, in order.
+    pub tags: Vec<(String, String)>,
+    /// The starting position in notation, or `None` for the usual start.
+    pub position: Option<String>,
+    pub moves: Vec<Move>,
+    pub result: GameResult,
+}
+
+impl GameRecord {
+    /// Creates a record from a string of moves played from the usual start.
+    pub fn from_moves_str(moves: &str) -> RecordResult<GameRecord> {
+        let builder = GameBuilder::default().with_moves_str(moves)?;
+        Ok(GameRecord {
+            moves: builder.moves().to_vec(),
+            ..GameRecord::default()
+        })
+    }
+
+    /// Returns the value of a tag.
+    pub fn tag(&self, key: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Sets the value of a tag.
+    pub fn set_tag(&mut self, key: &str, value: &str) {
+        match self.tags.iter_mut().find(|(k, _)| k == key) {
+            Some((_, v)) => *v = value.to_string(),
+            None => self.tags.push((key.to_string(), value.to_string())),
+        }
+    }
+
+    /// Returns a game builder in the starting position of the record.
+    pub fn start(&self) -> RecordResult<GameBuilder> {
+        match &self.position {
+            Some(position) => Ok(GameBuilder::from_notation(position)?),
+            None => Ok(GameBuilder::default()),
+        }
+    }
+
+    /// Returns the positions of the game, starting with the starting position
+    /// and ending with the position after the last move.
+    pub fn games(&self) -> RecordResult<Vec<Game>> {
+        let mut builder = self.start()?;
+        let mut games = vec![builder.game().clone()];
+        for m in &self.moves {
+            builder = builder.with_move(*m)?;
+            games.push(builder.game().clone());
+        }
+        Ok(games)
+    }
+
+    /// Returns the moves as a string that can be parsed again.
+    pub fn moves_str(&self) -> String {
+        moves_to_string(&self.moves)
+    }
+
+    /// Loads all records from a file.
+    pub fn load<P: AsRef<Path>>(path: P) -> RecordResult<Vec<GameRecord>> {
+        parse_records(&fs::read_to_string(path)?)
+    }
+
+    /// Saves records to a file.
+    pub fn save<P: AsRef<Path>>(path: P, records: &[GameRecord]) -> RecordResult<()> {
+        let records: Vec<String> = records.iter().map(|r| r.to_string()).collect();
+        fs::write(path, records.join("\n"))?;
+        Ok(())
+    }
+}
+
+/// Writes moves in pairs separated by semicolons, e.g. "6L 5R; 3L".
+pub fn moves_to_string(moves: &[Move]) -> String {
+    let pairs: Vec<String> = moves
+        .chunks(2)
+        .map(|pair| {
+            let pair: Vec<String> = pair.iter().map(|m| m.to_string()).collect();
+            pair.join(" ")
+        })
+        .collect();
+    pairs.join("; ")
+}
+
+/// Parses all records in a string.
+pub fn parse_records(s: &str) -> RecordResult<Vec<GameRecord>> {
+    let mut records = vec![];
+    let mut tags: Vec<(String, String)> = vec![];
+    // The lines of moves with their line numbers, to name them in errors.
+    let mut moves: Vec<(usize, &str)> = vec![];
+    for (number, line) in s.lines().map(str::trim).enumerate() {
+        if line.starts_with('[') {
+            if !moves.is_empty() {
+                // A new record starts.
+                records.push(build_record(std::mem::take(&mut tags), &moves)?);
+                moves.clear();
+            }
+            tags.push(parse_tag(line)?);
+        } else if !line.is_empty() && !line.starts_with('#') {
+            moves.push((number + 1, line));
+        }
+    }
+    if !tags.is_empty() || !moves.is_empty() {
+        records.push(build_record(tags, &moves)?);
+    }
+    Ok(records)
+}
+
+fn parse_tag(line: &str) -> RecordResult<(String, String)> {
+    let invalid = || RecordError::InvalidTag(line.to_string());
+    let inner = line
+        .strip_prefix('[')
+        .and_then(|l| l.strip_suffix(']'))
+        .ok_or_else(invalid)?;
+    let (key, value) = inner.split_once(' ').ok_or_else(invalid)?;
+    let value = value
+        .trim()
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .ok_or_else(invalid)?;
+    Ok((key.to_string(), value.to_string()))
+}
+
+fn build_record(tags: Vec<(String, String)>, moves: &[(usize, &str)]) -> RecordResult<GameRecord> {
+    let mut record = GameRecord::default();
+    for (key, value) in tags {
+        match key.as_str() {
+            "Position" => record.position = Some(value),
+            "Result" => record.result = value.parse()?,
+            _ => record.tags.push((key, value)),
+        }
+    }
+    let mut builder = record.start()?;
+    for (number, line) in moves {
+        builder = builder
+            .with_moves_str(line)
+            .map_err(|e| RecordError::InvalidMoves(*number, e))?;
+    }
+    record.moves = builder.moves().to_vec();
+    Ok(record)
+}
+
+impl FromStr for GameRecord {
+    type Err = RecordError;
+
+    fn from_str(s: &str) -> RecordResult<GameRecord> {
+        parse_records(s)?
+            .into_iter()
+            .next()
+            .ok_or(RecordError::NoRecords)
+    }
+}
+
+impl Display for GameRecord {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        for (key, value) in &self.tags {
+            writeln!(f, "[{} \"{}\"]", key, value)?;
+        }
+        if let Some(position) = &self.position {
+            writeln!(f, "[Position \"{}\"]", position)?;
+        }
+        writeln!(f, "[Result \"{}\"]", self.result)?;
+        writeln!(f)?;
+        writeln!(f, "{}", self.moves_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_record_roundtrip() -> RecordResult<()> {
+        let mut record = GameRecord::from_moves_str("7L 5R; 6L 5R; 2 6R")?;
+        record.set_tag("Event", "test");
+        record.result = GameResult::SecondPlayerWins;
+        let parsed: GameRecord = record.to_string().parse()?;
+        assert_eq!(parsed.tag("Event"), Some("test"));
+        assert_eq!(parsed.result, GameResult::SecondPlayerWins);
+        assert_eq!(parsed.moves, record.moves);
+        assert_eq!(parsed.moves_str(), "7L 5R; 6L 5R; 2 6R");
+        assert_eq!(parsed.games()?.len(), 7);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_multiple_records() -> RecordResult<()> {
+        let s = "[Round \"1\"]\n[Result \"1-0\"]\n\n6L 5R;\n3L\n\n\
+                 [Round \"2\"]\n[Result \"*\"]\n\n7L\n";
+        let records = parse_records(s)?;
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].moves.len(), 3);
+        assert_eq!(records[1].tag("Round"), Some("2"));
+        assert_eq!(records[1].result, GameResult::Unknown);
+        Ok(())
+    }
+
+    #[test]
+    fn test_record_with_position() -> RecordResult<()> {
+        let s = "[Position \"0,1,0,0,0,0,0,0,0,0,0,0,0,0,3,0/\
+                 2,0,0,0,0,0,2,0,2,0,0,0,0,0,0,0 0/0 00\"]\n\nB7R A1R\n";
+        let record: GameRecord = s.parse()?;
+        assert!(record.position.is_some());
+        assert_eq!(record.moves_str(), "B7R A1R");
+        Ok(())
+    }
+
+    #[test]
+    fn test_record_errors() {
+        match "[Result \"2-0\"]\n".parse::<GameRecord>() {
+            Err(e) => assert_eq!(e.to_string(), "InvalidResult: 2-0"),
+            _ => panic!(),
+        }
+        match "[Result]\n".parse::<GameRecord>() {
+            Err(e) => assert_eq!(e.to_string(), "InvalidTag: [Result]"),
+            _ => panic!(),
+        }
+        match "".parse::<GameRecord>() {
+            Err(e) => assert_eq!(e.to_string(), "NoRecords"),
+            _ => panic!(),
+        }
+        match "[Result \"*\"]\n\n6L 5R;\n3l\n".parse::<GameRecord>() {
+            Err(e) => assert!(e
+                .to_string()
+                .starts_with("InvalidMoves: line 4: InvalidToken: 3l")),
+            _ => panic!(),
+        }
+        assert!(GameRecord::from_moves_str("6L 5R; 3l").is_err());
+    }
+}
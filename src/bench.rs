@@ -0,0 +1,150 @@
+//! Search benchmark over a fixed set of positions.
+//!
+//! Every position is searched single-threaded to a fixed depth, so the number
+//! of searched nodes only depends on the search and the evaluation. The
+//! signature hashes the node counts of all positions and changes whenever
+//! the search behaves differently.
+
+use crate::bao::notation;
+use crate::config::SearchConfig;
+use crate::error::SearchResult;
+use crate::search::negamax;
+use std::fmt::{Display, Formatter};
+use std::time::{Duration, Instant};
+
+/// The benchmark positions, from the opening over the namua and mtaji phases
+/// to the ending.
+pub const POSITIONS: [&str; 10] = [
+    "0,0,0,0,6,2,2,0,0,0,0,0,0,0,0,0/0,0,0,0,6,2,2,0,0,0,0,0,0,0,0,0 22/22 11",
+    "0,0,0,0,7,1,0,1,2,1,1,1,0,0,0,0/0,0,0,0,7,1,2,0,1,1,0,0,0,0,0,0 19/19 11",
+    "1,0,2,1,9,0,0,0,2,1,1,0,0,0,0,1/0,3,0,1,1,1,0,1,1,2,1,1,1,1,1,1 15/15 10",
+    "0,1,1,0,0,10,0,0,0,0,0,1,1,1,1,1/1,1,1,3,0,8,4,0,1,0,2,0,2,0,2,0 11/11 00",
+    "0,0,0,0,12,1,0,1,7,0,4,0,2,0,1,1/0,0,0,0,11,0,1,1,7,1,3,1,1,1,0,0 4/4 11",
+    "0,0,3,0,21,0,1,1,2,1,1,0,0,0,0,1/0,4,0,1,4,1,0,5,0,1,3,1,2,1,3,3 2/2 10",
+    "0,3,1,1,13,1,1,0,1,0,5,1,3,1,2,2/1,1,1,1,0,0,0,1,9,1,5,1,3,1,2,2 0/0 11",
+    "3,4,4,1,2,0,7,1,3,3,0,1,3,5,0,0/2,1,5,4,0,0,0,0,0,1,3,2,0,1,5,3 0/0 00",
+    "1,0,0,2,0,9,0,2,0,0,1,3,0,0,5,5/6,1,1,3,2,3,3,2,2,3,0,0,0,4,1,5 0/0 00",
+    "0,0,0,0,0,0,0,1,2,0,0,1,2,1,0,1/1,2,7,2,1,8,6,0,3,1,5,6,2,5,3,4 0/0 00",
+];
+
+/// The default benchmark depth.
+pub const DEFAULT_DEPTH: u8 = 14;
+
+/// The search of a single benchmark position.
+#[derive(Clone, Debug)]
+pub struct BenchPosition {
+    pub position: &'static str,
+    pub nodes: u64,
+    pub time: Duration,
+}
+
+/// The result of a benchmark.
+#[derive(Clone, Debug)]
+pub struct Bench {
+    pub depth: u8,
+    pub positions: Vec<BenchPosition>,
+}
+
+impl Bench {
+    /// Returns the number of nodes searched in all positions.
+    pub fn nodes(&self) -> u64 {
+        self.positions.iter().map(|p| p.nodes).sum()
+    }
+
+    /// Returns the time spent on all positions.
+    pub fn time(&self) -> Duration {
+        self.positions.iter().map(|p| p.time).sum()
+    }
+
+    /// Returns the number of nodes searched per second.
+    pub fn nps(&self) -> u64 {
+        (self.nodes() as f64 / self.time().as_secs_f64().max(1e-9)) as u64
+    }
+
+    /// Returns the FNV-1a hash of the depth and the node counts of all positions.
+    pub fn signature(&self) -> u64 {
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        let counts = self.positions.iter().map(|p| p.nodes);
+        for value in std::iter::once(self.depth as u64).chain(counts) {
+            for byte in value.to_le_bytes() {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(0x0100_0000_01b3);
+            }
+        }
+        hash
+    }
+}
+
+impl Display for Bench {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        for (i, position) in self.positions.iter().enumerate() {
+            writeln!(
+                f,
+                "Position {:>2}: {:>10} nodes {:>10.3?}  {}",
+                i + 1,
+                position.nodes,
+                position.time,
+                position.position
+            )?;
+        }
+        writeln!(f, "Depth:     {}", self.depth)?;
+        writeln!(f, "Nodes:     {}", self.nodes())?;
+        writeln!(f, "Time:      {:.3?}", self.time())?;
+        writeln!(f, "NPS:       {}", self.nps())?;
+        write!(f, "Signature: {:016x}", self.signature())
+    }
+}
+
+/// Runs the benchmark.
+///
+/// # Arguments
+///
+/// * `config` - The search configuration. The depth is replaced by `depth`
+///   and the search runs single-threaded without time or node limits.
+/// * `depth` - The search depth.
+///
+/// # Returns
+///
+/// The node counts and times of all positions.
+pub fn run(config: &SearchConfig, depth: u8) -> SearchResult<Bench> {
+    let mut config = config.clone();
+    config.max_depth = depth;
+    config.num_threads = 1;
+    config.max_time_ms = None;
+    config.max_nodes = None;
+
+    let mut positions = vec![];
+    for position in POSITIONS {
+        let game = notation::from_notation(position).expect("Invalid benchmark position");
+        let start = Instant::now();
+        let mut nodes = 0;
+        negamax::search_with_observer(config.clone(), game, |info| nodes = info.nodes)?;
+        positions.push(BenchPosition {
+            position,
+            nodes,
+            time: start.elapsed(),
+        });
+    }
+    Ok(Bench { depth, positions })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bench_is_deterministic() -> SearchResult<()> {
+        for position in POSITIONS {
+            assert!(!notation::from_notation(position)
+                .expect("Invalid position")
+                .is_over());
+        }
+        let config = SearchConfig::new(3, 4, Some(1));
+        let first = run(&config, 3)?;
+        let second = run(&config, 3)?;
+        assert_eq!(first.positions.len(), POSITIONS.len());
+        assert!(first.nodes() > 0);
+        assert_eq!(first.signature(), second.signature());
+        Ok(())
+    }
+}
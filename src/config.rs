@@ -1,5 +1,46 @@
 //! Configuration for the search algorithm.
 
+use crate::error::{SearchError, SearchResult};
+use crate::search::book::Book;
+use crate::search::eval_params::EvalParams;
+use crate::search::mcts::MctsConfig;
+use crate::search::strength::Strength;
+use crate::search::tablebase::Tablebases;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// The search algorithm.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum SearcherKind {
+    /// Iterative deepening negamax with alpha-beta pruning.
+    #[default]
+    Negamax,
+    /// Monte Carlo tree search.
+    Mcts,
+}
+
+impl FromStr for SearcherKind {
+    type Err = SearchError;
+
+    fn from_str(s: &str) -> SearchResult<SearcherKind> {
+        match s {
+            "negamax" => Ok(SearcherKind::Negamax),
+            "mcts" => Ok(SearcherKind::Mcts),
+            _ => Err(SearchError::InvalidSearcher(s.to_string())),
+        }
+    }
+}
+
+impl Display for SearcherKind {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            SearcherKind::Negamax => write!(f, "negamax"),
+            SearcherKind::Mcts => write!(f, "mcts"),
+        }
+    }
+}
+
 /// Configuration for the search algorithm.
 #[derive(Debug, Clone)]
 pub struct SearchConfig {
@@ -9,6 +50,24 @@ pub struct SearchConfig {
     pub num_threads: u8,
     /// The maximum time to search in milliseconds.
     pub max_time_ms: Option<u32>,
+    /// The maximum number of nodes to search.
+    pub max_nodes: Option<u64>,
+    /// The evaluation parameters.
+    pub eval_params: Arc<EvalParams>,
+    /// Whether to resolve captures with a quiescence search at the leaves.
+    pub quiescence: bool,
+    /// Whether to order moves by their move ordering bonuses and the tt move.
+    pub move_ordering: bool,
+    /// The opening book to play from before searching.
+    pub book: Option<Arc<Book>>,
+    /// The endgame tablebases to probe during the search.
+    pub tablebases: Option<Arc<Tablebases>>,
+    /// The search algorithm.
+    pub searcher: SearcherKind,
+    /// The configuration of the Monte Carlo tree search.
+    pub mcts: MctsConfig,
+    /// The strength model of the negamax search, none to always play the best move.
+    pub strength: Option<Arc<Strength>>,
 }
 
 impl Default for SearchConfig {
@@ -25,37 +84,89 @@ impl SearchConfig {
             max_depth,
             num_threads,
             max_time_ms,
+            max_nodes: None,
+            eval_params: Arc::new(EvalParams::default()),
+            quiescence: false,
+            move_ordering: true,
+            book: None,
+            tablebases: None,
+            searcher: SearcherKind::Negamax,
+            mcts: MctsConfig::default(),
+            strength: None,
         }
     }
 
+    /// Returns the search configuration with the given evaluation parameters.
+    pub fn with_eval_params(mut self, eval_params: EvalParams) -> Self {
+        self.eval_params = Arc::new(eval_params);
+        self
+    }
+
+    /// Returns the search configuration with the given opening book.
+    pub fn with_book(mut self, book: Book) -> Self {
+        self.book = Some(Arc::new(book));
+        self
+    }
+
+    /// Returns the search configuration with the given endgame tablebases.
+    pub fn with_tablebases(mut self, tablebases: Tablebases) -> Self {
+        self.tablebases = Some(Arc::new(tablebases));
+        self
+    }
+
     /// Creates a new search configuration based on a difficulty level.
     pub fn new_from_difficulty(difficulty: u8) -> Self {
-        let max_depth = match difficulty {
-            0..=1 => 1,
-            2 => 2,
-            3 => 10,
-            4 => 10,
-            5 => 10,
-            6 => 10,
-            7 => 20,
-            8 => 20,
-            9 => 20,
-            10 => 40,
-            _ => 40,
-        };
-        let max_time_ms = match difficulty {
-            1 => Some(5),
-            2 => Some(10),
-            3 => Some(20),
-            4 => Some(50),
-            5 => Some(100),
-            6 => Some(200),
-            7 => Some(500),
-            8 => Some(1000),
-            9 => Some(2000),
-            10 => Some(3000),
-            _ => None,
+        SearchConfig::new(20, 1, None).with_difficulty(difficulty)
+    }
+
+    /// Returns the search configuration with the depth and strength model of
+    /// a difficulty level from 1 to 10. Higher levels search at least as deep
+    /// and pick worse moves less often.
+    pub fn with_difficulty(mut self, difficulty: u8) -> Self {
+        // (depth, top k, temperature, noise rate, noise)
+        let (max_depth, top_k, temperature, noise_rate, noise) = match difficulty {
+            0..=1 => (1, 8, 6.0, 0.4, 8.0),
+            2 => (1, 6, 4.0, 0.3, 6.0),
+            3 => (2, 5, 3.0, 0.25, 5.0),
+            4 => (2, 4, 2.0, 0.2, 4.0),
+            5 => (3, 4, 1.5, 0.15, 3.5),
+            6 => (3, 3, 1.0, 0.1, 3.0),
+            7 => (4, 3, 0.8, 0.08, 2.5),
+            8 => (5, 3, 0.6, 0.06, 2.0),
+            9 => (6, 2, 0.4, 0.04, 1.5),
+            _ => (7, 2, 0.3, 0.02, 1.0),
         };
-        SearchConfig::new(max_depth, 1, max_time_ms)
+        self.max_depth = max_depth;
+        self.max_time_ms = None;
+        self.strength = Some(Arc::new(Strength {
+            top_k,
+            temperature,
+            noise_rate,
+            noise,
+        }));
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_difficulty_levels_get_stronger() {
+        let levels: Vec<SearchConfig> = (1..=10).map(SearchConfig::new_from_difficulty).collect();
+        for pair in levels.windows(2) {
+            let (weaker, stronger) = (&pair[0], &pair[1]);
+            let (a, b) = (
+                weaker.strength.as_ref().unwrap(),
+                stronger.strength.as_ref().unwrap(),
+            );
+            assert!(weaker.max_depth <= stronger.max_depth);
+            assert!(a.top_k >= b.top_k);
+            assert!(a.temperature > b.temperature);
+            assert!(a.noise_rate > b.noise_rate);
+            assert!(a.noise > b.noise);
+        }
     }
 }
@@ -1,20 +1,13 @@
 use crate::bao::game::Game;
 use crate::bao::pv::PVLine;
+use crate::render::terminal::BoardRenderer;
 
 pub fn clear_terminal() {
     print!("\x1B[2J\x1B[1;1H");
 }
 
 pub fn print_game(game: &Game) {
-    println!("{}", game);
-}
-
-pub fn print_game_mirror(game: &Game) {
-    let mut mirror = game.clone();
-    let tmp = mirror.current_player.clone();
-    mirror.current_player = mirror.other_player.clone();
-    mirror.other_player = tmp;
-    println!("{}", mirror);
+    println!("{}", BoardRenderer::new().render_game(game));
 }
 
 pub fn print_pvlines(pvlines: &[PVLine]) {
@@ -8,6 +8,12 @@ use std::sync::mpsc::SendError;
 pub enum SearchError {
     #[error("SendPVLineError: {0}")]
     SendPVLineError(#[from] SendError<PVLine>),
+    #[error("InvalidSearcher: {0}")]
+    InvalidSearcher(String),
+    #[error("InvalidPlayout: {0}")]
+    InvalidPlayout(String),
+    #[error("IllegalMove: {0}")]
+    IllegalMove(String),
 }
 
 /// Search result type.
@@ -24,6 +30,8 @@ pub enum MoveFactoryError {
     ParseInvalidDirection(String),
     #[error("ParseInvalidLength: {0}")]
     ParseInvalidLength(usize),
+    #[error("ParseInvalidCharacters: {0}")]
+    ParseInvalidCharacters(String),
 }
 
 /// Move factory result type.
@@ -36,7 +44,207 @@ pub enum GameBuilderError {
     InvalidMove(#[from] MoveFactoryError),
     #[error("IllegalMove: {0}")]
     IllegalMove(String),
+    #[error("InvalidToken: {0} ({1})")]
+    InvalidToken(String, String),
+    #[error("InvalidPosition: {0}")]
+    InvalidPosition(#[from] NotationError),
 }
 
 /// Game factory result type
 pub type GameBuilderResult<T> = Result<T, GameBuilderError>;
+
+/// Position notation error type.
+#[derive(Debug, thiserror::Error)]
+pub enum NotationError {
+    #[error("ParseError: {0}")]
+    ParseError(#[from] ParseIntError),
+    #[error("InvalidFieldCount: {0}")]
+    InvalidFieldCount(usize),
+    #[error("InvalidField: {0}")]
+    InvalidField(String),
+    #[error("InvalidPitCount: {0}")]
+    InvalidPitCount(usize),
+    #[error("InvalidNyumba: {0}")]
+    InvalidNyumba(String),
+}
+
+/// Position notation result type.
+pub type NotationResult<T> = Result<T, NotationError>;
+
+/// Game record error type.
+#[derive(Debug, thiserror::Error)]
+pub enum RecordError {
+    #[error("IoError: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("InvalidTag: {0}")]
+    InvalidTag(String),
+    #[error("InvalidResult: {0}")]
+    InvalidResult(String),
+    #[error("InvalidGame: {0}")]
+    InvalidGame(#[from] GameBuilderError),
+    #[error("InvalidMoves: line {0}: {1}")]
+    InvalidMoves(usize, GameBuilderError),
+    #[error("NoRecords")]
+    NoRecords,
+}
+
+/// Game record result type.
+pub type RecordResult<T> = Result<T, RecordError>;
+
+/// Evaluation parameters error type.
+#[derive(Debug, thiserror::Error)]
+pub enum EvalParamsError {
+    #[error("IoError: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("InvalidLine: {0}")]
+    InvalidLine(String),
+    #[error("UnknownKey: {0}")]
+    UnknownKey(String),
+    #[error("InvalidValue: {0} = {1}")]
+    InvalidValue(String, String),
+    #[error("InvalidLength: {0} has {1} values")]
+    InvalidLength(String, usize),
+}
+
+/// Evaluation parameters result type.
+pub type EvalParamsResult<T> = Result<T, EvalParamsError>;
+
+/// Tuner error type.
+#[derive(Debug, thiserror::Error)]
+pub enum TuneError {
+    #[error("IoError: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("InvalidLine: {0}: {1}")]
+    InvalidLine(usize, String),
+    #[error("InvalidPosition: {0}")]
+    InvalidPosition(#[from] NotationError),
+    #[error("NoPositions")]
+    NoPositions,
+}
+
+/// Tuner result type.
+pub type TuneResult<T> = Result<T, TuneError>;
+
+/// Opening book error type.
+#[derive(Debug, thiserror::Error)]
+pub enum BookError {
+    #[error("IoError: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("InvalidHeader")]
+    InvalidHeader,
+    #[error("InvalidLength: {0} bytes")]
+    InvalidLength(usize),
+    #[error("InvalidMove: index {0}, flags {1:#06b}")]
+    InvalidMove(u8, u8),
+    #[error("RecordError: {0}")]
+    RecordError(#[from] RecordError),
+}
+
+/// Opening book result type.
+pub type BookResult<T> = Result<T, BookError>;
+
+/// Tablebase error type.
+#[derive(Debug, thiserror::Error)]
+pub enum TablebaseError {
+    #[error("IoError: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("InvalidHeader")]
+    InvalidHeader,
+    #[error("InvalidLength: {0} bytes")]
+    InvalidLength(usize),
+    #[error("TooManySeeds: {0}")]
+    TooManySeeds(u8),
+    #[error("WrongSeeds: {0} has {1} seeds")]
+    WrongSeeds(String, u8),
+    #[error("NotFound: {0}")]
+    NotFound(String),
+}
+
+/// Tablebase result type.
+pub type TablebaseResult<T> = Result<T, TablebaseError>;
+
+/// Play error type.
+#[derive(Debug, thiserror::Error)]
+pub enum PlayError {
+    #[error("InvalidSide: {0}")]
+    InvalidSide(String),
+}
+
+/// Play result type.
+pub type PlayResult<T> = Result<T, PlayError>;
+
+/// Shell error type.
+#[derive(Debug, thiserror::Error)]
+pub enum ShellError {
+    #[error("ReadlineError: {0}")]
+    ReadlineError(#[from] rustyline::error::ReadlineError),
+}
+
+/// Shell result type.
+pub type ShellResult<T> = Result<T, ShellError>;
+
+/// Puzzle error type.
+#[derive(Debug, thiserror::Error)]
+pub enum PuzzleError {
+    #[error("RecordError: {0}")]
+    RecordError(#[from] RecordError),
+    #[error("SearchError: {0}")]
+    SearchError(#[from] SearchError),
+}
+
+/// Puzzle result type.
+pub type PuzzleResult<T> = Result<T, PuzzleError>;
+
+/// Test suite error type.
+#[derive(Debug, thiserror::Error)]
+pub enum TestSuiteError {
+    #[error("IoError: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("InvalidLine: {0}: {1}")]
+    InvalidLine(usize, String),
+    #[error("InvalidPosition: {0}")]
+    InvalidPosition(#[from] NotationError),
+    #[error("InvalidMove: {0}")]
+    InvalidMove(#[from] MoveFactoryError),
+    #[error("SearchError: {0}")]
+    SearchError(#[from] SearchError),
+}
+
+/// Test suite result type.
+pub type TestSuiteResult<T> = Result<T, TestSuiteError>;
+
+/// Review error type.
+#[derive(Debug, thiserror::Error)]
+pub enum ReviewError {
+    #[error("RecordError: {0}")]
+    RecordError(#[from] RecordError),
+    #[error("Unsearched: {0}")]
+    Unsearched(String),
+    #[error("SearchError: {0}")]
+    SearchError(#[from] SearchError),
+}
+
+/// Review result type.
+pub type ReviewResult<T> = Result<T, ReviewError>;
+
+/// Match error type.
+#[derive(Debug, thiserror::Error)]
+pub enum MatchError {
+    #[error("InvalidEngine: {0}")]
+    InvalidEngine(String),
+    #[error("InvalidOpening: {0}: {1}")]
+    InvalidOpening(usize, RecordError),
+    #[error("EvalParamsError: {0}")]
+    EvalParamsError(#[from] EvalParamsError),
+    #[error("RecordError: {0}")]
+    RecordError(#[from] RecordError),
+    #[error("BookError: {0}")]
+    BookError(#[from] BookError),
+    #[error("TablebaseError: {0}")]
+    TablebaseError(#[from] TablebaseError),
+    #[error("SearchError: {0}")]
+    SearchError(#[from] SearchError),
+}
+
+/// Match result type.
+pub type MatchResult<T> = Result<T, MatchError>;
@@ -6,10 +6,17 @@
 )]
 
 pub mod bao;
+pub mod bench;
 pub mod config;
 mod display;
 pub mod error;
 #[macro_use]
 mod macros;
 pub mod play;
+pub mod puzzles;
+pub mod render;
+pub mod review;
 pub mod search;
+pub mod self_play;
+pub mod shell;
+pub mod testsuite;
@@ -1,9 +1,33 @@
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
+use kikande::bao::game::Game;
 use kikande::bao::game_builder::GameBuilder;
-use kikande::config::SearchConfig;
-use kikande::error::SearchResult;
-use kikande::play;
+use kikande::bao::moves::Move;
+use kikande::bao::record::{self, GameRecord};
+use kikande::bench;
+use kikande::config::{SearchConfig, SearcherKind};
+use kikande::error::EvalParamsResult;
+use kikande::play::{self, PlayOptions, Side};
+use kikande::puzzles::{self, PuzzleConfig};
+use kikande::render::html::Viewer;
+use kikande::render::svg::SvgRenderer;
+use kikande::render::terminal::BoardRenderer;
+use kikande::review::{self, Annotation, Review, ReviewThresholds};
+use kikande::search::analyze;
+use kikande::search::book::Book;
+use kikande::search::eval_params::EvalParams;
+use kikande::search::mcts::Playout;
 use kikande::search::negamax;
+use kikande::search::searcher;
+use kikande::search::solver;
+use kikande::search::tablebase::Tablebases;
+use kikande::search::tune::{self, TuneConfig};
+use kikande::self_play::{self, Engine, MatchConfig, SprtConfig};
+use kikande::shell;
+use kikande::testsuite;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
 
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
@@ -21,6 +45,42 @@ enum Commands {
         /// Set the difficulty level [default: 5]
         #[arg(short, long, default_value_t = 5)]
         difficulty: u8,
+
+        /// Load the evaluation parameters from a file.
+        #[arg(long)]
+        eval_params: Option<PathBuf>,
+
+        /// Play from an opening book.
+        #[arg(long)]
+        book: Option<PathBuf>,
+
+        /// Probe the endgame tablebases in this directory.
+        #[arg(long)]
+        tablebases: Option<PathBuf>,
+
+        /// The search algorithm: negamax or mcts [default: negamax]
+        #[arg(long, default_value_t = SearcherKind::Negamax)]
+        searcher: SearcherKind,
+
+        /// The side you play: first, second or random [default: first]
+        #[arg(long, default_value_t = Side::First)]
+        side: Side,
+
+        /// Watch the computer play against itself. South plays at --difficulty.
+        #[arg(long, conflicts_with = "side")]
+        spectate: bool,
+
+        /// The difficulty level of North when spectating [default: --difficulty]
+        #[arg(long, requires = "spectate")]
+        north_difficulty: Option<u8>,
+
+        /// Animate every turn step by step with this delay in milliseconds between steps.
+        #[arg(long)]
+        animate_ms: Option<u64>,
+
+        /// Leave empty pits blank.
+        #[arg(long)]
+        hide_zeros: bool,
     },
 
     /// Search for the best move.
@@ -33,31 +93,1126 @@ enum Commands {
 
         #[arg(short, long)]
         max_time_ms: Option<u32>,
+
+        /// Load the evaluation parameters from a file.
+        #[arg(long)]
+        eval_params: Option<PathBuf>,
+
+        /// Play from an opening book.
+        #[arg(long)]
+        book: Option<PathBuf>,
+
+        /// Probe the endgame tablebases in this directory.
+        #[arg(long)]
+        tablebases: Option<PathBuf>,
+
+        /// The search algorithm: negamax or mcts [default: negamax]
+        #[arg(long, default_value_t = SearcherKind::Negamax)]
+        searcher: SearcherKind,
+
+        /// The number of Monte Carlo tree search playouts [default: 10000]
+        #[arg(long, default_value_t = 10_000)]
+        iterations: u32,
+
+        /// The playout policy of the Monte Carlo tree search: random or heuristic [default: random]
+        #[arg(long, default_value_t = Playout::Random)]
+        playout: Playout,
+
+        /// The position in notation [default: the starting position]
+        #[arg(long)]
+        position: Option<String>,
+
+        /// Moves played from the position, e.g. "6L 5R; 3L".
+        #[arg(long, default_value = "")]
+        moves: String,
+
+        /// Search a position of a game record.
+        #[arg(long, conflicts_with_all = ["position", "moves"])]
+        record: Option<PathBuf>,
+
+        /// The number of the game in the record file, starting at 1 [default: 1]
+        #[arg(long, default_value_t = 1, requires = "record")]
+        game: usize,
+
+        /// The number of moves of the record to play before searching [default: all]
+        #[arg(long, requires = "record")]
+        ply: Option<usize>,
+    },
+
+    /// Tune the evaluation parameters on labelled positions.
+    Tune {
+        /// File with one position and the result for the player to move per line.
+        #[arg(short, long)]
+        positions: PathBuf,
+
+        /// Write the tuned evaluation parameters to this file.
+        #[arg(short, long)]
+        output: PathBuf,
+
+        /// Start from the evaluation parameters in this file.
+        #[arg(long)]
+        eval_params: Option<PathBuf>,
+
+        /// The maximum number of passes over all parameters [default: 100]
+        #[arg(short, long, default_value_t = 100)]
+        iterations: u32,
+
+        /// The initial step size of the local search [default: 0.1]
+        #[arg(short, long, default_value_t = 0.1)]
+        step: f32,
+    },
+
+    /// Play a match between two engine configurations.
+    Match {
+        /// The first engine, e.g. "depth=6,time=100,eval=params.txt,quiescence=on".
+        #[arg(long)]
+        engine1: Engine,
+
+        /// The second engine, in the same format as the first.
+        #[arg(long)]
+        engine2: Engine,
+
+        /// The number of games to play [default: 100]
+        #[arg(short, long, default_value_t = 100)]
+        games: u32,
+
+        /// File with one opening per line, given as moves.
+        #[arg(long)]
+        openings: Option<PathBuf>,
+
+        /// The number of random opening plies without an openings file [default: 4]
+        #[arg(long, default_value_t = 4)]
+        random_plies: u8,
+
+        /// Games that are not decided after this many plies are drawn [default: 400]
+        #[arg(long, default_value_t = 400)]
+        max_plies: u32,
+
+        /// Save the game records to this file.
+        #[arg(long)]
+        records: Option<PathBuf>,
+
+        /// Save the labelled positions of all games to this file for tuning.
+        #[arg(long)]
+        positions: Option<PathBuf>,
+
+        /// The Elo difference of the SPRT null hypothesis [default: 0]
+        #[arg(long, default_value_t = 0.0)]
+        elo0: f64,
+
+        /// The Elo difference of the SPRT alternative hypothesis [default: 10]
+        #[arg(long, default_value_t = 10.0)]
+        elo1: f64,
+    },
+
+    /// Generate endgame tablebases for mtaji positions without nyumba.
+    Tablebase {
+        /// The maximum number of seeds on the board [default: 6]
+        #[arg(short, long, default_value_t = 6)]
+        seeds: u8,
+
+        /// Write the tablebases to this directory.
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+
+    /// Prove a win or loss for the player to move.
+    Solve {
+        /// The position in notation [default: the starting position]
+        #[arg(long)]
+        position: Option<String>,
+
+        /// Moves played from the position, e.g. "6L 5R; 3L".
+        #[arg(long, default_value = "")]
+        moves: String,
+
+        /// The maximum number of nodes to expand [default: 1000000]
+        #[arg(short, long, default_value_t = 1_000_000)]
+        nodes: u64,
+    },
+
+    /// Search a position and export the searched tree.
+    Trace {
+        /// The position in notation [default: the starting position]
+        #[arg(long)]
+        position: Option<String>,
+
+        /// Moves played from the position, e.g. "6L 5R; 3L".
+        #[arg(long, default_value = "")]
+        moves: String,
+
+        #[arg(short, long, default_value_t = 4)]
+        depth: u8,
+
+        /// The number of plies below the root to record.
+        #[arg(long, default_value_t = 2)]
+        trace_depth: usize,
+
+        /// Load the evaluation parameters from a file.
+        #[arg(long)]
+        eval_params: Option<PathBuf>,
+
+        /// The output format [default: dot]
+        #[arg(long, default_value = "dot")]
+        format: TraceFormat,
+
+        /// Write the trace to a file instead of printing it.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Search every legal move of a position.
+    Analyze {
+        /// The position in notation [default: the starting position]
+        #[arg(long)]
+        position: Option<String>,
+
+        /// Moves played from the position, e.g. "6L 5R; 3L".
+        #[arg(long, default_value = "")]
+        moves: String,
+
+        #[arg(short, long, default_value_t = 8)]
+        depth: u8,
+
+        #[arg(short, long, default_value_t = 1)]
+        threads: u8,
+
+        /// The search time per move.
+        #[arg(short, long)]
+        max_time_ms: Option<u32>,
+
+        /// Load the evaluation parameters from a file.
+        #[arg(long)]
+        eval_params: Option<PathBuf>,
+
+        /// Sort the moves by score or by move [default: score]
+        #[arg(long, default_value = "score")]
+        sort: AnalysisOrder,
+
+        /// Print the analysis as JSON.
+        #[arg(long)]
+        json: bool,
     },
+
+    /// Review a game and annotate every move.
+    Review {
+        /// The game record file.
+        #[arg(long, conflicts_with = "moves")]
+        record: Option<PathBuf>,
+
+        /// The number of the game in the record file, starting at 1 [default: 1]
+        #[arg(long, default_value_t = 1)]
+        game: usize,
+
+        /// The moves of the game, e.g. "6L 5R; 3L".
+        #[arg(long)]
+        moves: Option<String>,
+
+        #[arg(short, long, default_value_t = 6)]
+        depth: u8,
+
+        #[arg(short, long, default_value_t = 1)]
+        threads: u8,
+
+        /// The search time per move.
+        #[arg(short, long)]
+        max_time_ms: Option<u32>,
+
+        /// Load the evaluation parameters from a file.
+        #[arg(long)]
+        eval_params: Option<PathBuf>,
+
+        /// Print the review as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Find tactical puzzles in game records or self-play games.
+    Puzzles {
+        /// Game record files to scan. Without records, self-play games are scanned.
+        #[arg(short, long)]
+        records: Vec<PathBuf>,
+
+        /// The number of self-play games to play [default: 10]
+        #[arg(short, long, default_value_t = 10)]
+        games: u32,
+
+        /// The number of random opening plies of the self-play games [default: 4]
+        #[arg(long, default_value_t = 4)]
+        random_plies: u8,
+
+        /// The search depth to scan positions and play games with [default: 4]
+        #[arg(short, long, default_value_t = 4)]
+        depth: u8,
+
+        /// The search depth to verify puzzles with [default: 8]
+        #[arg(long, default_value_t = 8)]
+        verify_depth: u8,
+
+        /// The minimum number of seeds the solution has to gain [default: 6]
+        #[arg(long, default_value_t = 6)]
+        min_gain: i32,
+
+        /// Write the puzzles to this file.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Run a test suite of positions with expected best moves.
+    Testsuite {
+        /// The test suite file.
+        suite: PathBuf,
+
+        #[arg(short, long)]
+        depth: Option<u8>,
+
+        #[arg(short, long)]
+        max_time_ms: Option<u32>,
+
+        /// The maximum number of nodes per position.
+        #[arg(short, long)]
+        nodes: Option<u64>,
+
+        #[arg(short, long, default_value_t = 1)]
+        threads: u8,
+
+        /// Load the evaluation parameters from a file.
+        #[arg(long)]
+        eval_params: Option<PathBuf>,
+    },
+
+    /// Search a fixed set of positions and report the nodes per second.
+    Bench {
+        #[arg(short, long, default_value_t = bench::DEFAULT_DEPTH)]
+        depth: u8,
+
+        /// Load the evaluation parameters from a file.
+        #[arg(long)]
+        eval_params: Option<PathBuf>,
+    },
+
+    /// Draw a position as an SVG diagram.
+    Render {
+        /// The position in notation [default: the starting position]
+        #[arg(long)]
+        position: Option<String>,
+
+        /// Moves played from the position, e.g. "6L 5R; 3L".
+        #[arg(long, default_value = "")]
+        moves: String,
+
+        /// A move to draw with its sowing path, e.g. A5R.
+        #[arg(long = "move")]
+        m: Option<String>,
+
+        /// Draw single seeds instead of seed counts.
+        #[arg(long)]
+        seeds: bool,
+
+        /// Only draw the arrow of the move, not its sowing path.
+        #[arg(long, requires = "m")]
+        no_path: bool,
+
+        /// Write the diagram to a file instead of printing it.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Export a game or a principal variation as a self-contained HTML viewer.
+    Html {
+        /// The game record file.
+        #[arg(long, conflicts_with_all = ["moves", "position"])]
+        record: Option<PathBuf>,
+
+        /// The number of the game in the record file, starting at 1 [default: 1]
+        #[arg(long, default_value_t = 1)]
+        game: usize,
+
+        /// The position in notation [default: the starting position]
+        #[arg(long)]
+        position: Option<String>,
+
+        /// Moves played from the position, e.g. "6L 5R; 3L".
+        #[arg(long, default_value = "")]
+        moves: String,
+
+        /// Review the game to show evaluations and annotations.
+        #[arg(long, conflicts_with = "pv")]
+        review: bool,
+
+        /// Search the final position and show the principal variation instead.
+        #[arg(long)]
+        pv: bool,
+
+        #[arg(short, long, default_value_t = 6)]
+        depth: u8,
+
+        #[arg(short, long, default_value_t = 1)]
+        threads: u8,
+
+        /// The search time per position.
+        #[arg(short, long)]
+        max_time_ms: Option<u32>,
+
+        /// Load the evaluation parameters from a file.
+        #[arg(long)]
+        eval_params: Option<PathBuf>,
+
+        /// Write the viewer to a file instead of printing it.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Analyze positions interactively.
+    Shell {
+        /// The search depth of `go` and `analyze`.
+        #[arg(short, long, default_value_t = 8)]
+        depth: u8,
+
+        #[arg(short, long, default_value_t = 1)]
+        threads: u8,
+
+        /// The search time per search.
+        #[arg(short, long)]
+        max_time_ms: Option<u32>,
+
+        /// Load the evaluation parameters from a file.
+        #[arg(long)]
+        eval_params: Option<PathBuf>,
+    },
+
+    /// Build or inspect an opening book.
+    Book {
+        #[command(subcommand)]
+        command: BookCommands,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum AnalysisOrder {
+    Score,
+    Move,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum TraceFormat {
+    Dot,
+    Json,
 }
 
-fn main() -> SearchResult<()> {
-    let cli = Cli::parse();
+#[derive(Subcommand)]
+enum BookCommands {
+    /// Build a book from game records or, without records, by searching every
+    /// position up to the given number of plies.
+    Build {
+        /// Write the book to this file.
+        #[arg(short, long)]
+        output: PathBuf,
+
+        /// Game record files to build the book from.
+        #[arg(short, long)]
+        records: Vec<PathBuf>,
+
+        /// The number of plies to store [default: 4]
+        #[arg(short, long, default_value_t = 4)]
+        plies: u8,
+
+        /// The search depth per position [default: 12]
+        #[arg(short, long, default_value_t = 12)]
+        depth: u8,
+
+        /// The search time per position.
+        #[arg(short, long)]
+        max_time_ms: Option<u32>,
+
+        #[arg(short, long, default_value_t = 1)]
+        threads: u8,
+
+        /// Load the evaluation parameters from a file.
+        #[arg(long)]
+        eval_params: Option<PathBuf>,
+    },
+
+    /// Show the book moves of a position.
+    Dump {
+        /// The book file.
+        book: PathBuf,
+
+        /// The position in notation [default: the starting position]
+        #[arg(long)]
+        position: Option<String>,
+
+        /// Moves played from the position, e.g. "6L 5R; 3L".
+        #[arg(long, default_value = "")]
+        moves: String,
+    },
+}
+
+fn main() {
+    if let Err(e) = run(Cli::parse()) {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run(cli: Cli) -> Result<(), Box<dyn Error>> {
     match cli.command {
-        Commands::Play { difficulty } => play::play(difficulty),
+        Commands::Play {
+            difficulty,
+            eval_params,
+            book,
+            tablebases,
+            searcher,
+            side,
+            spectate,
+            north_difficulty,
+            animate_ms,
+            hide_zeros,
+        } => {
+            let mut config = SearchConfig::new_from_difficulty(difficulty)
+                .with_eval_params(load_eval_params(eval_params)?);
+            config.searcher = searcher;
+            if let Some(path) = book {
+                config = config.with_book(Book::load(path)?);
+            }
+            if let Some(path) = tablebases {
+                config = config.with_tablebases(Tablebases::load(path)?);
+            }
+            let options = PlayOptions {
+                animation: animate_ms.map(Duration::from_millis),
+                renderer: BoardRenderer::new().with_hide_zeros(hide_zeros),
+            };
+            match spectate {
+                true => {
+                    let north = config
+                        .clone()
+                        .with_difficulty(north_difficulty.unwrap_or(difficulty));
+                    play::spectate(config, north, options)
+                }
+                false => play::play(config, side, options),
+            }
+        }
         Commands::Search {
             depth,
             threads,
             max_time_ms,
+            eval_params,
+            book,
+            tablebases,
+            searcher,
+            iterations,
+            playout,
+            position,
+            moves,
+            record,
+            game,
+            ply,
         } => {
             // Default search config.
-            let mut config = SearchConfig::default();
+            let mut config =
+                SearchConfig::default().with_eval_params(load_eval_params(eval_params)?);
+            config.searcher = searcher;
+            config.mcts.iterations = iterations;
+            config.mcts.playout = playout;
+            if let Some(path) = book {
+                config = config.with_book(Book::load(path)?);
+            }
+            if let Some(path) = tablebases {
+                config = config.with_tablebases(Tablebases::load(path)?);
+            }
             config.max_depth = depth.unwrap_or(config.max_depth);
             config.num_threads = threads.unwrap_or(config.num_threads);
             config.max_time_ms = max_time_ms;
 
-            // New game.
-            let game = kikande::bao_game!();
+            // The position to search and the moves that led to it.
+            let (game, played) = match record {
+                Some(path) => {
+                    let record = load_record(path, game)?;
+                    let ply = ply.unwrap_or(record.moves.len());
+                    if ply > record.moves.len() {
+                        return Err(format!(
+                            "The game has {} moves, there is no ply {}",
+                            record.moves.len(),
+                            ply
+                        )
+                        .into());
+                    }
+                    let game = record.games()?.swap_remove(ply);
+                    (game, record.moves[..ply].to_vec())
+                }
+                None => {
+                    let builder = GameBuilder::from_game(load_game(position, "")?);
+                    let builder = builder.with_moves_str(&moves)?;
+                    (builder.game().clone(), builder.moves().to_vec())
+                }
+            };
+            if game.is_over() {
+                return Err("The game is over, the player to move has lost".into());
+            }
 
             // Start search.
-            negamax::search(config, game, true)?;
+            let pvline = searcher::new_searcher(&config).search(&game, true)?;
+            println!("PV: {}", record::moves_to_string(&pvline.moves));
+            if !played.is_empty() {
+                let line = [played, pvline.moves].concat();
+                println!("Line: {}", record::moves_to_string(&line));
+            }
+        }
+        Commands::Tune {
+            positions,
+            output,
+            eval_params,
+            iterations,
+            step,
+        } => {
+            let positions = tune::load_positions(positions)?;
+            let config = TuneConfig {
+                max_iterations: iterations,
+                step,
+                ..TuneConfig::default()
+            };
+            let params = load_eval_params(eval_params)?;
+            let (params, error) =
+                tune::tune_with_observer(&positions, params, &config, |progress| {
+                    match progress.iteration {
+                        0 => println!(
+                            "Positions: {}, K: {:.4}, initial error: {:.6}",
+                            progress.positions, progress.k, progress.error
+                        ),
+                        iteration => println!(
+                            "Iteration {}: error={:.6}, step={}",
+                            iteration, progress.error, progress.step
+                        ),
+                    }
+                })?;
+            params.save(&output)?;
+            println!("Final error: {:.6}", error);
+            println!("Saved evaluation parameters to {}", output.display());
+        }
+        Commands::Match {
+            engine1,
+            engine2,
+            games,
+            openings,
+            random_plies,
+            max_plies,
+            records,
+            positions,
+            elo0,
+            elo1,
+        } => {
+            let openings = match openings {
+                Some(path) => load_openings(path)?,
+                None => vec![],
+            };
+            let config = MatchConfig {
+                num_games: games,
+                openings,
+                random_plies,
+                max_plies,
+                sprt: SprtConfig {
+                    elo0,
+                    elo1,
+                    ..SprtConfig::default()
+                },
+            };
+            let (stats, games) =
+                self_play::play_match_with_observer(&engine1, &engine2, &config, |progress| {
+                    println!(
+                        "Game {}: {} ({} plies), {}",
+                        progress.round,
+                        progress.record.result,
+                        progress.record.moves.len(),
+                        progress.stats
+                    )
+                })?;
+            let (lower, upper) = self_play::sprt_bounds(&config.sprt);
+            println!("{}", stats);
+            println!(
+                "SPRT [{}, {}]: LLR {:.2} ({:.2}, {:.2}), {:?}",
+                elo0,
+                elo1,
+                stats.llr(&config.sprt),
+                lower,
+                upper,
+                stats.sprt(&config.sprt)
+            );
+            if let Some(path) = records {
+                GameRecord::save(&path, &games)?;
+                println!("Saved games to {}", path.display());
+            }
+            if let Some(path) = positions {
+                let lines = self_play::labelled_positions(&games)?;
+                fs::write(&path, lines.join("\n") + "\n")?;
+                println!("Saved {} positions to {}", lines.len(), path.display());
+            }
+        }
+        Commands::Tablebase { seeds, output } => {
+            let tablebases = Tablebases::generate(seeds)?;
+            tablebases.save(&output)?;
+            for tablebase in tablebases.tablebases() {
+                let (wins, losses, draws) = tablebase.stats();
+                println!(
+                    "{} seeds: {} positions, {} wins, {} losses, {} draws",
+                    tablebase.seeds,
+                    tablebase.len(),
+                    wins,
+                    losses,
+                    draws
+                );
+            }
+            println!("Saved tablebases to {}", output.display());
+        }
+        Commands::Solve {
+            position,
+            moves,
+            nodes,
+        } => {
+            let game = load_game(position, &moves)?;
+            let solution = solver::solve(&game, nodes);
+            println!("{}", game);
+            println!("Result for the player to move: {}", solution.proof);
+            println!("Nodes: {}", solution.nodes);
+            if !solution.line.is_empty() {
+                println!("Main line: {}", record::moves_to_string(&solution.line));
+            }
+        }
+        Commands::Trace {
+            position,
+            moves,
+            depth,
+            trace_depth,
+            eval_params,
+            format,
+            output,
+        } => {
+            let game = load_game(position, &moves)?;
+            let config =
+                SearchConfig::new(depth, 1, None).with_eval_params(load_eval_params(eval_params)?);
+            let trace =
+                negamax::trace(&config, &game, trace_depth).ok_or("The search stopped early")?;
+            let trace = match format {
+                TraceFormat::Dot => trace.to_dot(),
+                TraceFormat::Json => trace.to_json()?,
+            };
+            match output {
+                Some(output) => fs::write(output, trace)?,
+                None => print!("{}", trace),
+            }
         }
+        Commands::Analyze {
+            position,
+            moves,
+            depth,
+            threads,
+            max_time_ms,
+            eval_params,
+            sort,
+            json,
+        } => {
+            let game = load_game(position, &moves)?;
+            let config = SearchConfig::new(depth, threads, max_time_ms)
+                .with_eval_params(load_eval_params(eval_params)?);
+            let mut analysis = analyze::analyze(&config, &game)?;
+            if let AnalysisOrder::Move = sort {
+                analysis.sort_by_key(|a| (a.m.index, a.m.flags));
+            }
+            if json {
+                println!("{}", serde_json::to_string_pretty(&analysis)?);
+            } else {
+                println!("{}", game);
+                println!("Rank Move    Score    Loss PV");
+                let best = analysis.iter().find(|a| a.rank == 1).cloned();
+                for a in &analysis {
+                    if !a.searched {
+                        println!(
+                            "{:>4} {:>4} {:>8} {:>7} Not searched",
+                            "-",
+                            a.m.to_string(),
+                            "-",
+                            "-"
+                        );
+                        continue;
+                    }
+                    let loss = best.as_ref().map_or(0.0, |best| a.loss(best));
+                    println!(
+                        "{:>4} {:>4} {:>8.2} {:>7.2} {}",
+                        a.rank,
+                        a.m.to_string(),
+                        a.score,
+                        loss,
+                        record::moves_to_string(&a.pv.moves)
+                    );
+                }
+            }
+        }
+        Commands::Review {
+            record,
+            game,
+            moves,
+            depth,
+            threads,
+            max_time_ms,
+            eval_params,
+            json,
+        } => {
+            let record = match (record, moves) {
+                (Some(path), _) => load_record(path, game)?,
+                (None, Some(moves)) => GameRecord::from_moves_str(&moves)?,
+                (None, None) => return Err("Either --record or --moves is required".into()),
+            };
+            let config = SearchConfig::new(depth, threads, max_time_ms)
+                .with_eval_params(load_eval_params(eval_params)?);
+            let review = review::review(&record, &config, &ReviewThresholds::default())?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&review)?);
+            } else {
+                print_review(&review);
+            }
+        }
+        Commands::Puzzles {
+            records,
+            games,
+            random_plies,
+            depth,
+            verify_depth,
+            min_gain,
+            output,
+        } => {
+            let config = PuzzleConfig {
+                scan: SearchConfig::new(depth, 1, None),
+                verify: SearchConfig::new(verify_depth, 1, None),
+                min_gain,
+            };
+            let mut named_records = vec![];
+            for path in &records {
+                for (i, record) in GameRecord::load(path)?.into_iter().enumerate() {
+                    let name = format!("{} game {}", path.display(), i + 1);
+                    named_records.push((name, record));
+                }
+            }
+            let num_games = match records.is_empty() {
+                true => games,
+                false => 0,
+            };
+            for i in 0..num_games {
+                let opening = self_play::random_opening(random_plies);
+                let record = self_play::play_game(&opening, &config.scan, &config.scan, 400)?;
+                named_records.push((format!("self-play game {}", i + 1), record));
+            }
+
+            let mut lines = vec![];
+            for (name, record) in &named_records {
+                for puzzle in puzzles::scan_record(record, &config, name)? {
+                    println!("{}", puzzle);
+                    lines.push(puzzle.to_string());
+                }
+            }
+            println!(
+                "Found {} puzzles in {} games",
+                lines.len(),
+                named_records.len()
+            );
+            if let Some(path) = output {
+                fs::write(&path, lines.join("\n") + "\n")?;
+                println!("Saved puzzles to {}", path.display());
+            }
+        }
+        Commands::Testsuite {
+            suite,
+            depth,
+            max_time_ms,
+            nodes,
+            threads,
+            eval_params,
+        } => {
+            let positions = testsuite::load(suite)?;
+            let mut config = SearchConfig::new(depth.unwrap_or(u8::MAX), threads, max_time_ms)
+                .with_eval_params(load_eval_params(eval_params)?);
+            config.max_nodes = nodes;
+            if depth.is_none() && max_time_ms.is_none() && nodes.is_none() {
+                config.max_depth = 8;
+            }
+
+            let mut num_passed = 0;
+            let mut solve_time = Duration::ZERO;
+            for position in &positions {
+                let outcome = testsuite::run_test(position, &config)?;
+                let found = outcome.pvline.moves.first().map(Move::to_string);
+                let solved = match outcome.solve_time {
+                    Some(time) => format!("solved after {:?}", time),
+                    None => "not solved".to_string(),
+                };
+                println!(
+                    "{} {:<20} {:>4} {} ({} nodes, {:?})",
+                    if outcome.passed { "PASS" } else { "FAIL" },
+                    outcome.id,
+                    found.unwrap_or("-".to_string()),
+                    solved,
+                    outcome.nodes,
+                    outcome.time
+                );
+                num_passed += outcome.passed as usize;
+                solve_time += outcome.solve_time.unwrap_or_default();
+            }
+            println!(
+                "Score: {}/{} passed, total solve time {:?}",
+                num_passed,
+                positions.len(),
+                solve_time
+            );
+        }
+        Commands::Bench { depth, eval_params } => {
+            let config =
+                SearchConfig::new(depth, 1, None).with_eval_params(load_eval_params(eval_params)?);
+            println!("{}", bench::run(&config, depth)?);
+        }
+        Commands::Render {
+            position,
+            moves,
+            m,
+            seeds,
+            no_path,
+            output,
+        } => {
+            let game = load_game(position, &moves)?;
+            let m = match m {
+                Some(m) => {
+                    let builder = GameBuilder::from_game(game.clone()).with_move_str(&m)?;
+                    builder.moves().first().copied()
+                }
+                None => None,
+            };
+            let renderer = SvgRenderer {
+                draw_seeds: seeds,
+                sowing_path: !no_path,
+                ..SvgRenderer::default()
+            };
+            let svg = renderer.render(&game, m.as_ref());
+            match output {
+                Some(output) => fs::write(output, svg)?,
+                None => print!("{}", svg),
+            }
+        }
+        Commands::Html {
+            record,
+            game,
+            position,
+            moves,
+            review,
+            pv,
+            depth,
+            threads,
+            max_time_ms,
+            eval_params,
+            output,
+        } => {
+            let record = match record {
+                Some(path) => load_record(path, game)?,
+                None => {
+                    let start = load_game(position.clone(), "")?;
+                    let builder = GameBuilder::from_game(start).with_moves_str(&moves)?;
+                    GameRecord {
+                        position,
+                        moves: builder.moves().to_vec(),
+                        ..GameRecord::default()
+                    }
+                }
+            };
+            let config = SearchConfig::new(depth, threads, max_time_ms)
+                .with_eval_params(load_eval_params(eval_params)?);
+            let viewer = match (review, pv) {
+                (true, _) => {
+                    let review = review::review(&record, &config, &ReviewThresholds::default())?;
+                    Viewer::from_record(&record, Some(&review))?
+                }
+                (false, true) => {
+                    let game = record.games()?.pop().expect("No positions");
+                    let pvline = negamax::search(config, game.clone(), false)?;
+                    Viewer::from_pv(&game, &pvline)?
+                }
+                (false, false) => Viewer::from_record(&record, None)?,
+            };
+            let html = viewer.to_html()?;
+            match output {
+                Some(output) => fs::write(output, html)?,
+                None => print!("{}", html),
+            }
+        }
+        Commands::Shell {
+            depth,
+            threads,
+            max_time_ms,
+            eval_params,
+        } => {
+            let config = SearchConfig::new(depth, threads, max_time_ms)
+                .with_eval_params(load_eval_params(eval_params)?);
+            shell::run(config)?;
+        }
+        Commands::Book { command } => run_book_command(command)?,
     };
 
     Ok(())
 }
+
+/// Loads the evaluation parameters from a file or returns the defaults.
+fn load_eval_params(path: Option<PathBuf>) -> EvalParamsResult<EvalParams> {
+    match path {
+        Some(path) => EvalParams::load(path),
+        None => Ok(EvalParams::default()),
+    }
+}
+
+/// Builds or dumps an opening book.
+fn run_book_command(command: BookCommands) -> Result<(), Box<dyn Error>> {
+    match command {
+        BookCommands::Build {
+            output,
+            records,
+            plies,
+            depth,
+            max_time_ms,
+            threads,
+            eval_params,
+        } => {
+            let book = match records.is_empty() {
+                true => {
+                    let config = SearchConfig::new(depth, threads, max_time_ms)
+                        .with_eval_params(load_eval_params(eval_params)?);
+                    Book::from_search(&config, plies)?
+                }
+                false => {
+                    let mut book = Book::new();
+                    for path in records {
+                        for record in GameRecord::load(path)? {
+                            book.add_record(&record, plies as usize)?;
+                        }
+                    }
+                    book
+                }
+            };
+            book.save(&output)?;
+            println!(
+                "Saved {} positions with {} moves to {}",
+                book.len(),
+                book.num_entries(),
+                output.display()
+            );
+        }
+        BookCommands::Dump {
+            book,
+            position,
+            moves,
+        } => {
+            let book = Book::load(book)?;
+            let game = load_game(position, &moves)?;
+            println!(
+                "Book: {} positions, {} moves",
+                book.len(),
+                book.num_entries()
+            );
+            let mut entries = book.entries(&game).to_vec();
+            if entries.is_empty() {
+                println!("Position not in book");
+            }
+            entries.sort_by_key(|e| std::cmp::Reverse(e.weight));
+            let total: u32 = entries.iter().map(|e| e.weight).sum();
+            for e in entries {
+                println!(
+                    "{:>4} weight {:>6} ({:5.1}%) games {:>6} +{} ={} -{}",
+                    e.m.to_string(),
+                    e.weight,
+                    100.0 * e.weight as f64 / total.max(1) as f64,
+                    e.games(),
+                    e.wins,
+                    e.draws,
+                    e.losses
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Loads a game from a record file.
+///
+/// # Arguments
+///
+/// * `path` - The record file.
+/// * `game` - The number of the game in the file, starting at 1.
+fn load_record(path: PathBuf, game: usize) -> Result<GameRecord, Box<dyn Error>> {
+    let index = game
+        .checked_sub(1)
+        .ok_or("Games are numbered from 1, there is no game 0")?;
+    GameRecord::load(&path)?
+        .into_iter()
+        .nth(index)
+        .ok_or_else(|| format!("No game {} in {}", game, path.display()).into())
+}
+
+/// Loads openings, one string of moves per line.
+///
+/// # Arguments
+///
+/// * `path` - The openings file. Blank lines and lines starting with `#` are skipped.
+fn load_openings(path: PathBuf) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut openings = vec![];
+    for (number, line) in fs::read_to_string(&path)?
+        .lines()
+        .map(str::trim)
+        .enumerate()
+    {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        GameRecord::from_moves_str(line)
+            .map_err(|e| format!("{} line {}: {}", path.display(), number + 1, e))?;
+        openings.push(line.to_string());
+    }
+    Ok(openings)
+}
+
+/// Returns the position after playing the moves from the given position or
+/// from the starting position.
+fn load_game(position: Option<String>, moves: &str) -> Result<Game, Box<dyn Error>> {
+    let builder = match position {
+        Some(position) => GameBuilder::from_notation(&position)?,
+        None => GameBuilder::new(),
+    };
+    Ok(builder.with_moves_str(moves)?.build())
+}
+
+/// Prints the annotated moves and the accuracy of both players.
+fn print_review(review: &Review) {
+    for (ply, reviewed) in review.moves.iter().enumerate() {
+        let number = match ply % 2 {
+            0 => format!("{:>3}.", ply / 2 + 1),
+            _ => "    ".to_string(),
+        };
+        let m = format!("{}{}", reviewed.m, reviewed.annotation.symbol());
+        print!(
+            "{} {:<6} {:<10} {:>8.2}",
+            number, m, reviewed.annotation, reviewed.played.score
+        );
+        if reviewed.annotation != Annotation::Best {
+            print!(
+                "  better: {} ({:.2}) {}",
+                reviewed.best.m,
+                reviewed.best.score,
+                record::moves_to_string(&reviewed.best.pv.moves)
+            );
+        }
+        println!();
+    }
+    println!();
+    for (player, name) in ["Player 1", "Player 2"].iter().enumerate() {
+        println!(
+            "{}: accuracy {:.1}%, {} inaccuracies, {} mistakes, {} blunders",
+            name,
+            review.accuracy(player),
+            review.count(player, Annotation::Inaccuracy),
+            review.count(player, Annotation::Mistake),
+            review.count(player, Annotation::Blunder)
+        );
+    }
+}
@@ -1,113 +1,616 @@
 //! Play a game against the computer.
 
+use crate::bao::events::{TurnEvent, TurnTrace};
 use crate::bao::game::Game;
-use crate::bao::move_factory::MoveFactory;
+use crate::bao::move_factory::{self, MoveFactory};
 use crate::bao::moves::Move;
+use crate::bao::record::{GameRecord, GameResult};
 use crate::config::SearchConfig;
 use crate::display;
+use crate::error::{PlayError, PlayResult};
+use crate::render::terminal::{BoardMarks, BoardRenderer};
 use crate::search::negamax;
+use crate::search::searcher::{self, Searcher};
+use std::fmt::{Display, Formatter};
 use std::io;
+use std::str::FromStr;
+use std::thread;
+use std::time::Duration;
 
-/// Play a game against the computer.
+/// An absolute player of a game. South moves first.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Seat {
+    South,
+    North,
+}
+
+impl Seat {
+    /// Returns the other seat.
+    pub fn opponent(self) -> Seat {
+        match self {
+            Seat::South => Seat::North,
+            Seat::North => Seat::South,
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+impl Display for Seat {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Seat::South => write!(f, "South"),
+            Seat::North => write!(f, "North"),
+        }
+    }
+}
+
+/// The side the human plays.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Side {
+    /// Move first, as South.
+    #[default]
+    First,
+    /// Move second, as North.
+    Second,
+    /// Toss a coin.
+    Random,
+}
+
+impl Side {
+    /// Returns the seat of the side, tossing a coin for a random side.
+    pub fn seat(self) -> Seat {
+        match self {
+            Side::First => Seat::South,
+            Side::Second => Seat::North,
+            Side::Random if rand::random() => Seat::South,
+            Side::Random => Seat::North,
+        }
+    }
+}
+
+impl FromStr for Side {
+    type Err = PlayError;
+
+    fn from_str(s: &str) -> PlayResult<Side> {
+        match s {
+            "first" => Ok(Side::First),
+            "second" => Ok(Side::Second),
+            "random" => Ok(Side::Random),
+            _ => Err(PlayError::InvalidSide(s.to_string())),
+        }
+    }
+}
+
+impl Display for Side {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Side::First => write!(f, "first"),
+            Side::Second => write!(f, "second"),
+            Side::Random => write!(f, "random"),
+        }
+    }
+}
+
+/// A player taking part in a game, moved by a human or by the computer.
+struct Participant {
+    name: String,
+    searcher: Option<Box<dyn Searcher + Send>>,
+}
+
+impl Participant {
+    fn human() -> Participant {
+        Participant {
+            name: "you".to_string(),
+            searcher: None,
+        }
+    }
+
+    fn computer(config: &SearchConfig) -> Participant {
+        Participant {
+            name: "computer".to_string(),
+            searcher: Some(searcher::new_searcher(config)),
+        }
+    }
+}
+
+/// A command entered by the human instead of a move.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    /// Play a move.
+    Move(Move),
+    /// Take back the last full round.
+    Undo,
+    /// Show the move of a short search.
+    Hint,
+    /// List the legal moves.
+    Moves,
+    /// Save the game as a record.
+    Save(String),
+    /// Load a game from a record.
+    Load(String),
+    Resign,
+    /// Turn the board around.
+    Flip,
+    Help,
+}
+
+const HELP: &str = "Enter a move, e.g. 6L or A5R, or a command: undo, hint, moves, \
+                    save <file>, load <file>, resign, flip, help.";
+
+/// Parses the input of the human.
 ///
 /// # Arguments
 ///
-/// * `difficulty` - The difficulty level (1-10).
-pub fn play(difficulty: u8) {
-    let config = SearchConfig::new_from_difficulty(difficulty);
+/// * `game` - The current position, used to parse and check moves.
+/// * `input` - The input line.
+///
+/// # Returns
+///
+/// The command, or a message explaining why the input was rejected.
+pub fn parse_command(game: &Game, input: &str) -> Result<Command, String> {
+    let mut words = input.split_whitespace();
+    let command = words.next().ok_or(HELP)?;
+    let argument = words.next();
+    if words.next().is_some() {
+        return Err(format!("Too many arguments: {}", input.trim()));
+    }
+    let no_argument = |command: Command| match argument {
+        Some(argument) => Err(format!("{} takes no argument: {}", input.trim(), argument)),
+        None => Ok(command),
+    };
+    let file = |command: fn(String) -> Command| match argument {
+        Some(file) => Ok(command(file.to_string())),
+        None => Err(format!(
+            "Missing file name, e.g. `{} game.txt`.",
+            input.trim()
+        )),
+    };
+    match command {
+        "undo" => no_argument(Command::Undo),
+        "hint" => no_argument(Command::Hint),
+        "moves" => no_argument(Command::Moves),
+        "resign" => no_argument(Command::Resign),
+        "flip" => no_argument(Command::Flip),
+        "help" => no_argument(Command::Help),
+        "save" => file(Command::Save),
+        "load" => file(Command::Load),
+        _ if argument.is_some() => Err(format!("Unknown command: {}. {}", command, HELP)),
+        _ if !command.starts_with(|c: char| c.is_ascii_digit() || "AaBb".contains(c)) => {
+            Err(format!("Unknown command: {}. {}", command, HELP))
+        }
+        _ => parse_move(game, command).map(Command::Move),
+    }
+}
 
-    // Start a new game.
-    let mut game = Game::default();
-    loop {
-        // Display current board state.
-        display::clear_terminal();
-        display::print_game(&game);
+/// Parses a legal move.
+///
+/// # Arguments
+///
+/// * `game` - The current position.
+/// * `input` - The move, e.g. 6L or A5R.
+///
+/// # Returns
+///
+/// The move, or a message explaining why it is not a legal move.
+pub fn parse_move(game: &Game, input: &str) -> Result<Move, String> {
+    let mut factory = MoveFactory::new(game);
+    let m = factory
+        .parse_move(input)
+        .map_err(|e| move_factory::parse_error_message(input, &e))?;
+    match factory.explain_illegal_move(&m) {
+        None => Ok(m),
+        Some(reason) => Err(format!(
+            "{} is not a legal move: {}. Enter `moves` to list the legal moves.",
+            input, reason
+        )),
+    }
+}
 
-        // Get the player's move.
-        game = match player_turn(&game) {
-            Some(game) => game,
-            None => break, // Game over.
-        };
+/// How the board is shown during a game.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PlayOptions {
+    /// The delay between the steps of an animated turn, none to not animate.
+    pub animation: Option<Duration>,
+    /// The board renderer.
+    pub renderer: BoardRenderer,
+}
 
-        // Display current board state.
-        display::clear_terminal();
-        display::print_game_mirror(&game);
+/// The last turn of a game, to highlight what it changed.
+struct LastTurn {
+    seat: Seat,
+    before: Game,
+    trace: TurnTrace,
+}
 
-        game = match computer_turn(&game, &config) {
-            Some(game) => game,
-            None => break, // Game over.
+/// A game in progress between two participants, indexed by seat.
+struct Session {
+    participants: [Participant; 2],
+    /// The seat shown at the bottom of the board.
+    bottom: Seat,
+    /// The search configuration of hints.
+    hint_config: SearchConfig,
+    options: PlayOptions,
+    last_turn: Option<LastTurn>,
+    record: GameRecord,
+    game: Game,
+}
+
+impl Session {
+    fn new(participants: [Participant; 2], bottom: Seat, hint_config: SearchConfig) -> Self {
+        Session {
+            participants,
+            bottom,
+            hint_config,
+            options: PlayOptions::default(),
+            last_turn: None,
+            record: GameRecord::default(),
+            game: Game::default(),
+        }
+    }
+
+    /// Returns the seat to move. South makes the first move of a record.
+    fn to_move(&self) -> Seat {
+        match self.record.moves.len() % 2 {
+            0 => Seat::South,
+            _ => Seat::North,
+        }
+    }
+
+    fn is_human(&self, seat: Seat) -> bool {
+        self.participants[seat.index()].searcher.is_none()
+    }
+
+    fn name(&self, seat: Seat) -> String {
+        format!("{} ({})", seat, self.participants[seat.index()].name)
+    }
+
+    fn with_options(mut self, options: PlayOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    fn play_move(&mut self, m: Move) {
+        let seat = self.to_move();
+        let before = self.game.clone();
+        let trace = self.game.take_turn_traced(&m);
+        if let Some(delay) = self.options.animation {
+            self.animate(&before, seat, &trace, delay);
+        }
+        self.last_turn = Some(LastTurn {
+            seat,
+            before,
+            trace,
+        });
+        self.record.moves.push(m);
+    }
+
+    /// Shows the board after every step of a turn, highlighting the pit of
+    /// the step.
+    fn animate(&self, before: &Game, seat: Seat, trace: &TurnTrace, delay: Duration) {
+        let mut game = before.clone();
+        for event in trace.events.iter().filter(|&&e| e != TurnEvent::TurnEnded) {
+            event.apply(&mut game);
+            let marks = match event.pit() {
+                Some((index, opponent)) => {
+                    let owner = if opponent { seat.opponent() } else { seat };
+                    BoardMarks::active(index, owner != self.bottom)
+                }
+                None => BoardMarks::default(),
+            };
+            display::clear_terminal();
+            self.print_position(&game, seat, &marks);
+            println!("{} plays {}: {}", seat, trace.m, event);
+            thread::sleep(delay);
+        }
+    }
+
+    /// Replaces the game with a record, returning an error if it is invalid.
+    fn set_record(&mut self, record: GameRecord) -> Result<(), String> {
+        let games = record.games().map_err(|e| e.to_string())?;
+        self.game = games.last().expect("No positions").clone();
+        self.last_turn = None;
+        self.record = record;
+        Ok(())
+    }
+
+    /// Takes back moves until the human is to move again, taking back at
+    /// least one move of the human.
+    fn undo(&mut self) -> Result<String, String> {
+        let human = [Seat::South, Seat::North]
+            .into_iter()
+            .find(|&seat| self.is_human(seat))
+            .ok_or("There is nobody to undo for.")?;
+        let first_human_move = human.index();
+        if self.record.moves.len() <= first_human_move {
+            return Err("There is no move of yours to take back.".to_string());
+        }
+        let mut record = self.record.clone();
+        record.moves.pop();
+        while record.moves.len() % 2 != first_human_move {
+            record.moves.pop();
+        }
+        let taken_back = self.record.moves.len() - record.moves.len();
+        self.set_record(record)?;
+        Ok(format!("Took back {} moves.", taken_back))
+    }
+
+    /// Returns the record with the names of the participants and the result.
+    fn record(&self) -> GameRecord {
+        let mut record = self.record.clone();
+        record.set_tag("Event", "kikande play");
+        record.set_tag("First", &self.participants[0].name);
+        record.set_tag("Second", &self.participants[1].name);
+        if self.game.is_over() {
+            record.result = match self.to_move() {
+                Seat::South => GameResult::SecondPlayerWins,
+                Seat::North => GameResult::FirstPlayerWins,
+            };
+        }
+        record
+    }
+
+    fn hint(&self) -> Result<String, String> {
+        let pvline = negamax::search(self.hint_config.clone(), self.game.clone(), false)
+            .map_err(|e| e.to_string())?;
+        match pvline.moves.first() {
+            Some(m) => Ok(format!("Hint: {} (score {:.2})", m, pvline.value)),
+            None => Err("There is no hint, every move loses.".to_string()),
         }
     }
+
+    /// Prints the board the right way up for the bottom seat, marking the
+    /// pits the last turn changed.
+    fn print(&self) {
+        let marks = match &self.last_turn {
+            Some(last) => {
+                let mover_is_top = last.seat != self.bottom;
+                BoardMarks::last_turn(&last.before, &self.game, &last.trace, mover_is_top)
+            }
+            None => BoardMarks::default(),
+        };
+        self.print_position(&self.game, self.to_move(), &marks);
+    }
+
+    /// Prints a position with the given seat to move the right way up for
+    /// the bottom seat.
+    fn print_position(&self, game: &Game, to_move: Seat, marks: &BoardMarks) {
+        let (bottom_player, top_player) = match to_move == self.bottom {
+            true => (&game.current_player, &game.other_player),
+            false => (&game.other_player, &game.current_player),
+        };
+        let board = self.options.renderer.render(
+            bottom_player,
+            top_player,
+            &self.name(self.bottom),
+            &self.name(self.bottom.opponent()),
+            marks,
+        );
+        println!("{}", board);
+    }
+
+    /// Runs a command of the human and returns the feedback. Returns `None`
+    /// if the game ends.
+    fn run_command(&mut self, command: Command) -> Option<Result<String, String>> {
+        let feedback = match command {
+            Command::Move(m) => {
+                self.play_move(m);
+                Ok(format!("You played {}.", m))
+            }
+            Command::Undo => self.undo(),
+            Command::Hint => self.hint(),
+            Command::Moves => {
+                let mut factory = MoveFactory::new(&self.game);
+                let moves: Vec<String> = factory
+                    .get_legal_moves()
+                    .iter()
+                    .map(|m| m.to_string())
+                    .collect();
+                Ok(format!("Legal moves: {}", moves.join(" ")))
+            }
+            Command::Save(file) => GameRecord::save(&file, &[self.record()])
+                .map(|_| format!("Saved the game to {}.", file))
+                .map_err(|e| format!("Could not save the game to {}: {}", file, e)),
+            Command::Load(file) => match GameRecord::load(&file) {
+                Ok(records) if records.is_empty() => Err(format!("{} holds no game.", file)),
+                Ok(mut records) => self
+                    .set_record(records.swap_remove(0))
+                    .map(|_| format!("Loaded the game from {}.", file)),
+                Err(e) => Err(format!("Could not load a game from {}: {}", file, e)),
+            },
+            Command::Resign => {
+                let seat = self.to_move();
+                println!(
+                    "{} resigns. {} wins!",
+                    self.name(seat),
+                    self.name(seat.opponent())
+                );
+                return None;
+            }
+            Command::Flip => {
+                self.bottom = self.bottom.opponent();
+                Ok("Flipped the board.".to_string())
+            }
+            Command::Help => Ok(HELP.to_string()),
+        };
+        Some(feedback)
+    }
 }
 
-fn player_turn(game: &Game) -> Option<Game> {
-    // Get all legal moves.
-    let mut factory = MoveFactory::new(game);
-    let legal_moves = factory.get_legal_moves();
-    if legal_moves.is_empty() {
-        return None; // Game over.
+/// Play a game against the computer.
+///
+/// # Arguments
+///
+/// * `config` - The search configuration of the computer.
+/// * `side` - The side the human plays.
+/// * `options` - How the board is shown.
+pub fn play(config: SearchConfig, side: Side, options: PlayOptions) {
+    let human = side.seat();
+    let mut participants = [Participant::human(), Participant::computer(&config)];
+    if human == Seat::North {
+        participants.reverse();
     }
+    run(Session::new(participants, human, hint_config(&config)).with_options(options));
+}
 
-    // Prompt player for move.
-    prompt_player_move(game, legal_moves)
+/// Watch the computer play against itself.
+///
+/// # Arguments
+///
+/// * `south` - The search configuration of South, who moves first.
+/// * `north` - The search configuration of North.
+/// * `options` - How the board is shown.
+pub fn spectate(south: SearchConfig, north: SearchConfig, options: PlayOptions) {
+    let participants = [Participant::computer(&south), Participant::computer(&north)];
+    let session = Session::new(participants, Seat::South, hint_config(&south));
+    run(session.with_options(options));
 }
 
-fn prompt_player_move(game: &Game, legal_moves: &[Move]) -> Option<Game> {
-    let mut next_game = None;
-    while next_game.is_none() {
-        // Prompt player for move.
-        println!("Enter move: ");
-        let mut input = String::new();
-        if io::stdin().read_line(&mut input).is_err() {
-            continue;
+/// Returns the configuration of a short full-strength search for hints.
+fn hint_config(config: &SearchConfig) -> SearchConfig {
+    let mut hint_config = config.clone();
+    hint_config.max_depth = 20;
+    hint_config.max_time_ms = Some(500);
+    hint_config.strength = None;
+    hint_config
+}
+
+/// Plays a game until it is over, the human resigns or the input ends.
+fn run(mut session: Session) {
+    let mut feedback: Option<Result<String, String>> = None;
+    loop {
+        // Display current board state.
+        display::clear_terminal();
+        session.print();
+        match feedback.take() {
+            Some(Ok(message)) => println!("{}", message),
+            Some(Err(message)) => println!("Rejected: {}", message),
+            None => {}
         }
-        input = input.trim().to_string();
-        if input.is_empty() {
-            continue;
+        let to_move = session.to_move();
+        if session.game.is_over() {
+            println!("{} wins!", session.name(to_move.opponent()));
+            break;
         }
-        // Parse move.
-        let factory = MoveFactory::new(game);
-        let m = match factory.parse_move(&input) {
-            Ok(m) => m,
-            _ => continue,
+
+        feedback = match session.participants[to_move.index()].searcher.as_mut() {
+            Some(searcher) => match computer_turn(&session.game, searcher.as_mut(), to_move) {
+                Some(m) => {
+                    session.play_move(m);
+                    Some(Ok(format!("{} played {}.", to_move, m)))
+                }
+                None => break, // Game over.
+            },
+            None => {
+                // Prompt player for move.
+                println!("Enter move or command: ");
+                let mut input = String::new();
+                match io::stdin().read_line(&mut input) {
+                    Ok(0) | Err(_) => break, // The input ended.
+                    Ok(_) if input.trim().is_empty() => None,
+                    Ok(_) => match parse_command(&session.game, &input) {
+                        Ok(command) => match session.run_command(command) {
+                            Some(feedback) => Some(feedback),
+                            None => break, // Resigned.
+                        },
+                        Err(message) => Some(Err(message)),
+                    },
+                }
+            }
         };
-        // Check if move is legal.
-        if !legal_moves.iter().any(|sm| *sm == m) {
-            continue;
-        }
-        let mut game_after_move = game.clone();
-        game_after_move.take_turn(&m);
-        next_game = Some(game_after_move);
     }
-    next_game
 }
 
-fn computer_turn(game: &Game, config: &SearchConfig) -> Option<Game> {
-    // Create search node
-    let pvline = match negamax::search(config.clone(), game.clone(), false) {
+fn computer_turn(game: &Game, searcher: &mut dyn Searcher, seat: Seat) -> Option<Move> {
+    let pvline = match searcher.search(game, false) {
         Ok(pvline) => pvline,
         Err(_) => return None, // Game over.
     };
 
     // Show the computer's move.
-    display::clear_terminal();
-    display::print_game_mirror(game);
-    display::print_pvlines(&[pvline.clone()]);
-    println!("Your opponent will play: {}", pvline.moves.first()?);
+    display::print_pvlines(std::slice::from_ref(&pvline));
+    println!("{} will play: {}", seat, pvline.moves.first()?);
     println!("Press enter to continue...");
     io::stdin()
         .read_line(&mut String::new())
         .expect("read error");
 
-    // Play the move and return the next state.
+    // Only play legal moves.
     let next_move = pvline.moves.first().expect("No moves");
     let mut factory = MoveFactory::new(game);
-    for m in factory.get_legal_moves() {
-        if next_move == m {
-            let mut next_game = game.clone();
-            next_game.take_turn(m);
-            return Some(next_game);
+    factory
+        .get_legal_moves()
+        .iter()
+        .copied()
+        .find(|m| m == next_move)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_side() -> PlayResult<()> {
+        assert_eq!("first".parse::<Side>()?.seat(), Seat::South);
+        assert_eq!("second".parse::<Side>()?.seat(), Seat::North);
+        assert_eq!(Side::Random.to_string(), "random");
+        assert_eq!(Seat::North.opponent(), Seat::South);
+        match "third".parse::<Side>() {
+            Err(e) => assert_eq!(e.to_string(), "InvalidSide: third"),
+            _ => panic!(),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_command() {
+        let game = Game::new();
+        assert_eq!(parse_command(&game, "undo"), Ok(Command::Undo));
+        assert_eq!(
+            parse_command(&game, " save  game.txt\n"),
+            Ok(Command::Save("game.txt".to_string()))
+        );
+        assert_eq!(
+            parse_command(&game, "6L"),
+            Ok(Command::Move(Move::namua_relay_left(5)))
+        );
+        assert!(parse_command(&game, "load").is_err());
+        assert!(parse_command(&game, "flip over").is_err());
+        assert!(parse_command(&game, "castle").is_err());
+        assert_eq!(
+            parse_command(&game, "1L"),
+            Err(
+                "1L is not a legal move: the pit is empty. Enter `moves` to list the legal moves."
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            parse_command(&game, "9R"),
+            Err("There is no pit 9, the pits are numbered 1 to 8.".to_string())
+        );
+    }
+
+    #[test]
+    fn test_session_undo() {
+        let participants = [
+            Participant::computer(&SearchConfig::new(1, 1, None)),
+            Participant::human(),
+        ];
+        let mut session = Session::new(participants, Seat::North, SearchConfig::new(1, 1, None));
+        assert!(session.undo().is_err());
+        for m in ["7L", "5L", "6R"] {
+            let m = MoveFactory::new(&session.game)
+                .parse_move(m)
+                .expect("Invalid move");
+            session.play_move(m);
         }
+        // Take back the computer's 6R and the human's 5L.
+        assert_eq!(session.undo(), Ok("Took back 2 moves.".to_string()));
+        assert_eq!(session.record.moves.len(), 1);
+        assert_eq!(session.to_move(), Seat::North);
+        assert!(session.undo().is_err());
     }
-    None
 }
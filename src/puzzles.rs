@@ -0,0 +1,252 @@
+//! Tactical puzzles found in games.
+//!
+//! A puzzle is a position where exactly one move wins the game or a large
+//! amount of material. Puzzles are written one per line in the test suite
+//! format, with the solution as the best move:
+//!
+//! ```text
+//! <position> bm 5L; pv "5L 3R; 8"; gain 8; id "game 3 ply 17";
+//! ```
+
+use crate::bao::game::Game;
+use crate::bao::notation;
+use crate::bao::player::Player;
+use crate::bao::pv::PVLine;
+use crate::bao::record::{self, GameRecord};
+use crate::config::SearchConfig;
+use crate::error::PuzzleResult;
+use crate::search::analyze::{self, MoveAnalysis};
+use std::fmt::{Display, Formatter, Result};
+
+/// Configuration of the puzzle search.
+#[derive(Clone, Debug)]
+pub struct PuzzleConfig {
+    /// The search configuration to scan all positions with.
+    pub scan: SearchConfig,
+    /// The deeper search configuration to verify candidates with.
+    pub verify: SearchConfig,
+    /// The minimum number of seeds the solution has to gain, both in total and
+    /// compared to every other move.
+    pub min_gain: i32,
+}
+
+impl Default for PuzzleConfig {
+    fn default() -> Self {
+        PuzzleConfig {
+            scan: SearchConfig::new(4, 1, None),
+            verify: SearchConfig::new(8, 1, None),
+            min_gain: 6,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Puzzle {
+    /// The position in notation.
+    pub position: String,
+    /// The solution line, starting with the only good move.
+    pub solution: PVLine,
+    /// The seeds gained by the player to move along the solution line.
+    pub gain: i32,
+    /// Whether the solution wins the game.
+    pub wins: bool,
+    pub id: String,
+}
+
+impl Display for Puzzle {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        let gain = match self.wins {
+            true => "win".to_string(),
+            false => self.gain.to_string(),
+        };
+        write!(
+            f,
+            "{} bm {}; pv \"{}\"; gain {}; id \"{}\";",
+            self.position,
+            self.solution.moves[0],
+            record::moves_to_string(&self.solution.moves),
+            gain,
+            self.id
+        )
+    }
+}
+
+/// Returns the seeds of the player to move minus the seeds of the other
+/// player, counting both the board and the hand.
+fn material(game: &Game) -> i32 {
+    let seeds = |player: &Player| {
+        let board: i32 = player.mashumo.mashumo.iter().map(|&s| s as i32).sum();
+        board + player.seeds as i32
+    };
+    seeds(&game.current_player) - seeds(&game.other_player)
+}
+
+/// Returns the seeds gained by the player to move along a line.
+fn line_gain(game: &Game, pv: &PVLine) -> i32 {
+    let mut end = game.clone();
+    for m in &pv.moves {
+        end.take_turn(m);
+    }
+    let sign = match pv.moves.len() % 2 {
+        0 => 1,
+        _ => -1,
+    };
+    (sign * material(&end) - material(game)) / 2
+}
+
+/// Returns the best move and the seeds it gains if it is the only move that
+/// wins, or if it gains at least `min_gain` seeds more than every other move
+/// along their lines.
+fn unique_best<'a>(
+    game: &Game,
+    analysis: &'a [MoveAnalysis],
+    min_gain: i32,
+) -> Option<(&'a MoveAnalysis, i32)> {
+    // Without every move searched the best one may not be unique.
+    if analysis.iter().any(|a| !a.searched) {
+        return None;
+    }
+    let (best, others) = analysis.split_first()?;
+    if others.is_empty() {
+        return None;
+    }
+    let gain = line_gain(game, &best.pv);
+    if best.score == f32::INFINITY {
+        let unique = others.iter().all(|other| other.score < f32::INFINITY);
+        return unique.then_some((best, gain));
+    }
+    let other_gain = others
+        .iter()
+        .map(|other| line_gain(game, &other.pv))
+        .max()?;
+    (gain - other_gain >= min_gain).then_some((best, gain))
+}
+
+/// Checks whether a position is a puzzle.
+///
+/// # Arguments
+///
+/// * `game` - The position to check.
+/// * `config` - The puzzle configuration.
+/// * `id` - The id of the puzzle.
+pub fn find_puzzle(game: &Game, config: &PuzzleConfig, id: &str) -> PuzzleResult<Option<Puzzle>> {
+    if game.is_over() {
+        return Ok(None);
+    }
+    // Scan quickly, then verify with the deeper search.
+    let analysis = analyze::analyze(&config.scan, game)?;
+    if unique_best(game, &analysis, config.min_gain).is_none() {
+        return Ok(None);
+    }
+    let analysis = analyze::analyze(&config.verify, game)?;
+    let (best, gain) = match unique_best(game, &analysis, config.min_gain) {
+        Some(best) => best,
+        None => return Ok(None),
+    };
+    let wins = best.score == f32::INFINITY;
+    if !wins && gain < config.min_gain {
+        return Ok(None);
+    }
+    Ok(Some(Puzzle {
+        position: notation::to_notation(game),
+        solution: best.pv.clone(),
+        gain,
+        wins,
+        id: id.to_string(),
+    }))
+}
+
+/// Scans every position of a game for puzzles.
+///
+/// # Arguments
+///
+/// * `record` - The game to scan.
+/// * `config` - The puzzle configuration.
+/// * `name` - The name of the game in the puzzle ids.
+pub fn scan_record(
+    record: &GameRecord,
+    config: &PuzzleConfig,
+    name: &str,
+) -> PuzzleResult<Vec<Puzzle>> {
+    let mut puzzles = vec![];
+    for (ply, game) in record.games()?.iter().enumerate() {
+        let id = format!("{} ply {}", name, ply + 1);
+        if let Some(puzzle) = find_puzzle(game, config, &id)? {
+            puzzles.push(puzzle);
+        }
+    }
+    Ok(puzzles)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::bao::game_builder::GameBuilder;
+    use crate::bao::moves::Move;
+
+    #[test]
+    fn test_find_puzzle_winning_relay() -> PuzzleResult<()> {
+        // Only relaying from A2 to the right wins in three plies.
+        let game = notation::from_notation(
+            "0,3,1,0,0,0,0,0,0,0,0,0,0,0,0,0/0,0,0,0,0,3,0,0,0,0,0,0,0,0,0,0 0/0 00",
+        )
+        .unwrap();
+        let config = PuzzleConfig {
+            scan: SearchConfig::new(2, 1, None),
+            verify: SearchConfig::new(4, 1, None),
+            min_gain: 6,
+        };
+        let puzzle = find_puzzle(&game, &config, "test")?.expect("No puzzle");
+        assert!(puzzle.wins);
+        assert_eq!(puzzle.solution.moves[0], Move::mtaji_relay_right(1));
+        assert_eq!(
+            puzzle.to_string(),
+            "0,3,1,0,0,0,0,0,0,0,0,0,0,0,0,0/0,0,0,0,0,3,0,0,0,0,0,0,0,0,0,0 0/0 00 \
+             bm A2R; pv \"A2R A6R; A3R\"; gain win; id \"test\";"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_unique_best_compares_seeds() {
+        let game = bao_game!("6L", "5R");
+        let line = |m: Option<Move>, score: f32| MoveAnalysis {
+            m: m.unwrap_or(Move::namua_capture_left(2)),
+            score,
+            pv: PVLine {
+                moves: m.into_iter().collect(),
+                value: score,
+            },
+            rank: 1,
+            searched: true,
+        };
+        // The capture gains two seeds although the scores are close.
+        let analysis = [
+            line(Some(Move::namua_capture_right(2)), 0.5),
+            line(None, 0.4),
+        ];
+        assert_eq!(
+            unique_best(&game, &analysis, 2).map(|(_, gain)| gain),
+            Some(2)
+        );
+        assert!(unique_best(&game, &analysis, 3).is_none());
+        // Lines that gain the same seeds are no puzzle, whatever their scores.
+        let analysis = [
+            line(Some(Move::namua_capture_right(2)), 9.0),
+            line(Some(Move::namua_capture_left(2)), 0.0),
+        ];
+        assert!(unique_best(&game, &analysis, 1).is_none());
+    }
+
+    #[test]
+    fn test_find_puzzle_starting_position() -> PuzzleResult<()> {
+        let config = PuzzleConfig {
+            scan: SearchConfig::new(2, 1, None),
+            verify: SearchConfig::new(2, 1, None),
+            min_gain: 6,
+        };
+        assert!(find_puzzle(&Game::new(), &config, "start")?.is_none());
+        Ok(())
+    }
+}
@@ -0,0 +1,4 @@
+//! Board renderers.
+pub mod html;
+pub mod svg;
+pub mod terminal;
@@ -0,0 +1,362 @@
+//! Self-contained HTML viewer of games and principal variations.
+//!
+//! All positions are precomputed and embedded as JSON next to a small script
+//! that steps through them, so the page works offline without any files.
+
+use crate::bao::game::Game;
+use crate::bao::moves::Move;
+use crate::bao::notation;
+use crate::bao::player::Player;
+use crate::bao::pv::PVLine;
+use crate::bao::record::GameRecord;
+use crate::error::RecordResult;
+use crate::review::{Annotation, Review};
+use serde::Serialize;
+
+/// One player's side of a viewer position.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ViewerSide {
+    pub pits: [u8; 16],
+    pub seeds: u8,
+    pub nyumba: bool,
+}
+
+impl ViewerSide {
+    fn new(player: &Player) -> ViewerSide {
+        ViewerSide {
+            pits: player.mashumo.mashumo,
+            seeds: player.seeds,
+            nyumba: player.nyumba,
+        }
+    }
+}
+
+/// A position of the viewer, with the first player at the bottom.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ViewerPosition {
+    pub first: ViewerSide,
+    pub second: ViewerSide,
+    pub first_to_move: bool,
+}
+
+/// A move of the viewer with its evaluation and annotation.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ViewerMove {
+    #[serde(rename = "move")]
+    pub m: Move,
+    /// The evaluation for the first player after the move, e.g. "+1.25".
+    pub eval: Option<String>,
+    /// The best move if it was not played.
+    pub best: Option<Move>,
+    pub annotation: Option<Annotation>,
+    pub comment: Option<String>,
+}
+
+/// The data of an HTML viewer.
+#[derive(Clone, Debug, Serialize)]
+pub struct Viewer {
+    pub title: String,
+    pub first_name: String,
+    pub second_name: String,
+    pub result: String,
+    /// The positions from the start to the position after the last move.
+    pub positions: Vec<ViewerPosition>,
+    pub moves: Vec<ViewerMove>,
+}
+
+impl Viewer {
+    /// Creates a viewer of a game record. The first player is the player to
+    /// move in the starting position of the record.
+    ///
+    /// # Arguments
+    ///
+    /// * `record` - The game.
+    /// * `review` - A review of the game for evaluations and annotations.
+    pub fn from_record(record: &GameRecord, review: Option<&Review>) -> RecordResult<Viewer> {
+        let games = record.games()?;
+        let positions = games
+            .iter()
+            .enumerate()
+            .map(|(ply, game)| {
+                let (first, second) = match ply % 2 {
+                    0 => (&game.current_player, &game.other_player),
+                    _ => (&game.other_player, &game.current_player),
+                };
+                ViewerPosition {
+                    first: ViewerSide::new(first),
+                    second: ViewerSide::new(second),
+                    first_to_move: ply % 2 == 0,
+                }
+            })
+            .collect();
+
+        let moves = record
+            .moves
+            .iter()
+            .enumerate()
+            .map(|(ply, &m)| {
+                let reviewed = review.and_then(|review| review.moves.get(ply));
+                ViewerMove {
+                    m,
+                    eval: reviewed.map(|reviewed| {
+                        // The score is for the player who made the move.
+                        let sign = if ply % 2 == 0 { 1.0 } else { -1.0 };
+                        format!("{:+.2}", sign * reviewed.played.score)
+                    }),
+                    best: reviewed.map(|r| r.best.m).filter(|&best| best != m),
+                    annotation: reviewed.map(|reviewed| reviewed.annotation),
+                    comment: None,
+                }
+            })
+            .collect();
+
+        let title = match (record.tag("Event"), record.tag("Date")) {
+            (Some(event), Some(date)) => format!("{} ({})", event, date),
+            (Some(event), None) => event.to_string(),
+            _ => "Bao game".to_string(),
+        };
+        Ok(Viewer {
+            title,
+            first_name: record.tag("First").unwrap_or("South").to_string(),
+            second_name: record.tag("Second").unwrap_or("North").to_string(),
+            result: record.result.to_string(),
+            positions,
+            moves,
+        })
+    }
+
+    /// Creates a viewer of a principal variation. The first player is the
+    /// player to move in the position.
+    ///
+    /// # Arguments
+    ///
+    /// * `game` - The position the variation starts from.
+    /// * `pvline` - The principal variation.
+    pub fn from_pv(game: &Game, pvline: &PVLine) -> RecordResult<Viewer> {
+        let record = GameRecord {
+            position: Some(notation::to_notation(game)),
+            moves: pvline.moves.clone(),
+            ..GameRecord::default()
+        };
+        let mut viewer = Viewer::from_record(&record, None)?;
+        viewer.title = "Principal variation".to_string();
+        if let Some(first) = viewer.moves.first_mut() {
+            first.eval = Some(format!("{:+.2}", pvline.value));
+            first.comment = Some(format!(
+                "The variation is worth {:+.2} for {}.",
+                pvline.value, viewer.first_name
+            ));
+        }
+        Ok(viewer)
+    }
+
+    /// Writes the viewer as a single HTML page.
+    pub fn to_html(&self) -> serde_json::Result<String> {
+        // Keep the data from closing the script element.
+        let data = serde_json::to_string(self)?.replace("</", "<\\/");
+        Ok(TEMPLATE
+            .replace("__TITLE__", &escape_html(&self.title))
+            .replace("__DATA__", &data))
+    }
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+const TEMPLATE: &str = r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>__TITLE__</title>
+<style>
+body { font-family: sans-serif; background: #f4e3c1; color: #333; margin: 2em; }
+h1 { font-size: 1.4em; }
+#main { display: flex; gap: 2em; align-items: flex-start; flex-wrap: wrap; }
+.board { background: #b5793d; border: 2px solid #6b4321; border-radius: 8px; padding: 8px; }
+.row { display: flex; }
+.row.gap { margin-top: 12px; }
+.pit { width: 44px; height: 44px; margin: 3px; border-radius: 50%; background: #7a4a22;
+       color: #fff; display: flex; align-items: center; justify-content: center; font-size: 18px; }
+.pit.nyumba { border-radius: 6px; box-shadow: inset 0 0 0 3px #f4e3c1; }
+.pit.changed { background: #2e8b57; }
+.hand { margin: 4px 0; }
+.to-move { font-weight: bold; }
+#controls { margin: 10px 0; }
+#controls button { font-size: 16px; min-width: 3em; }
+#moves { max-height: 28em; overflow-y: auto; border-collapse: collapse; }
+#moves td { padding: 2px 8px; }
+#moves .move { cursor: pointer; }
+#moves .current { background: #f1c40f; }
+.blunder, .mistake { color: #c0392b; }
+.inaccuracy { color: #b9770e; }
+.best { color: #1e8449; }
+#comment { margin-top: 10px; max-width: 30em; }
+</style>
+</head>
+<body>
+<h1>__TITLE__</h1>
+<div id="main">
+<div>
+<div class="hand" id="second"></div>
+<div class="board" id="board"></div>
+<div class="hand" id="first"></div>
+<div id="controls">
+<button id="start">|&lt;</button>
+<button id="back">&lt;</button>
+<button id="forward">&gt;</button>
+<button id="end">&gt;|</button>
+</div>
+<div id="comment"></div>
+</div>
+<table id="moves"></table>
+</div>
+<script>
+const data = __DATA__;
+const symbols = { best: "!", good: "", inaccuracy: "?!", mistake: "?", blunder: "??" };
+let ply = 0;
+
+function pit(side, index, previous) {
+  const el = document.createElement("div");
+  el.className = "pit";
+  if (index === 4 && side.nyumba) el.classList.add("nyumba");
+  if (previous && previous.pits[index] !== side.pits[index]) el.classList.add("changed");
+  el.textContent = side.pits[index];
+  return el;
+}
+
+function row(side, indices, previous, gap) {
+  const el = document.createElement("div");
+  el.className = gap ? "row gap" : "row";
+  for (const index of indices) el.appendChild(pit(side, index, previous));
+  return el;
+}
+
+function hand(id, name, side, toMove) {
+  const el = document.getElementById(id);
+  el.textContent = name + ": " + side.seeds + " seeds in hand" + (side.nyumba ? ", nyumba standing" : "");
+  el.className = toMove ? "hand to-move" : "hand";
+}
+
+function show() {
+  const position = data.positions[ply];
+  const previous = ply > 0 ? data.positions[ply - 1] : null;
+  const board = document.getElementById("board");
+  const range = (from, to) => Array.from({ length: to - from }, (_, i) => from + i);
+  board.replaceChildren(
+    row(position.second, range(8, 16), previous && previous.second),
+    row(position.second, range(0, 8).reverse(), previous && previous.second),
+    row(position.first, range(0, 8), previous && previous.first, true),
+    row(position.first, range(8, 16).reverse(), previous && previous.first)
+  );
+  hand("second", data.second_name, position.second, !position.first_to_move);
+  hand("first", data.first_name, position.first, position.first_to_move);
+
+  for (const el of document.querySelectorAll("#moves .move")) {
+    el.classList.toggle("current", Number(el.dataset.ply) === ply);
+  }
+  const m = ply > 0 ? data.moves[ply - 1] : null;
+  let comment = "";
+  if (m) {
+    comment = "Move " + ply + ": " + m.move;
+    if (m.annotation) comment += " (" + m.annotation + ")";
+    if (m.eval) comment += ", evaluation " + m.eval;
+    if (m.best) comment += ", best was " + m.best;
+    if (m.comment) comment += ". " + m.comment;
+  } else if (ply === data.positions.length - 1) {
+    comment = "Result: " + data.result;
+  }
+  document.getElementById("comment").textContent = comment;
+}
+
+function go(target) {
+  ply = Math.max(0, Math.min(data.positions.length - 1, target));
+  show();
+}
+
+function moveList() {
+  const table = document.getElementById("moves");
+  for (let i = 0; i < data.moves.length; i += 2) {
+    const tr = document.createElement("tr");
+    const number = document.createElement("td");
+    number.textContent = i / 2 + 1 + ".";
+    tr.appendChild(number);
+    for (const j of [i, i + 1]) {
+      const td = document.createElement("td");
+      const m = data.moves[j];
+      if (m) {
+        td.className = "move " + (m.annotation || "");
+        td.dataset.ply = j + 1;
+        td.textContent = m.move + (m.annotation ? symbols[m.annotation] : "") + (m.eval ? " " + m.eval : "");
+        td.onclick = () => go(j + 1);
+      }
+      tr.appendChild(td);
+    }
+    table.appendChild(tr);
+  }
+  const tr = document.createElement("tr");
+  const td = document.createElement("td");
+  td.colSpan = 3;
+  td.textContent = data.result;
+  tr.appendChild(td);
+  table.appendChild(tr);
+}
+
+document.getElementById("start").onclick = () => go(0);
+document.getElementById("back").onclick = () => go(ply - 1);
+document.getElementById("forward").onclick = () => go(ply + 1);
+document.getElementById("end").onclick = () => go(data.positions.length - 1);
+document.addEventListener("keydown", (e) => {
+  if (e.key === "ArrowLeft") go(ply - 1);
+  if (e.key === "ArrowRight") go(ply + 1);
+  if (e.key === "Home") go(0);
+  if (e.key === "End") go(data.positions.length - 1);
+});
+moveList();
+show();
+</script>
+</body>
+</html>
+"##;
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::bao::moves::Move;
+
+    #[test]
+    fn test_viewer_from_record() -> RecordResult<()> {
+        let mut record = GameRecord::from_moves_str("6L 5R; 3L")?;
+        record.set_tag("Event", "Club <night>");
+        let viewer = Viewer::from_record(&record, None)?;
+        assert_eq!(viewer.positions.len(), 4);
+        assert_eq!(viewer.moves.len(), 3);
+        assert!(viewer.positions[0].first_to_move);
+        assert!(!viewer.positions[1].first_to_move);
+        // The first player placed a seed from the hand.
+        assert_eq!(viewer.positions[1].first.seeds, 21);
+        assert_eq!(viewer.positions[1].second.seeds, 22);
+
+        let html = viewer.to_html().expect("Failed to serialize");
+        assert!(html.contains("<title>Club &lt;night&gt;</title>"));
+        assert!(html.contains("\"move\":\"6L\""));
+        assert!(!html.contains("__DATA__"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_viewer_from_pv() -> RecordResult<()> {
+        let pvline = PVLine {
+            moves: vec![Move::namua_relay_left(5)],
+            value: 1.5,
+        };
+        let viewer = Viewer::from_pv(&Game::new(), &pvline)?;
+        assert_eq!(viewer.positions.len(), 2);
+        assert_eq!(viewer.moves[0].eval.as_deref(), Some("+1.50"));
+        Ok(())
+    }
+}
@@ -0,0 +1,295 @@
+//! SVG diagrams of positions.
+//!
+//! The player to move is drawn at the bottom, like the terminal board. A move
+//! can be drawn as an arrow from its pit, together with the path of every
+//! sowing lap of its turn and the captures in between.
+
+use crate::bao::events::TurnTrace;
+use crate::bao::game::Game;
+use crate::bao::moves::Move;
+use crate::bao::player::Player;
+use std::fmt::Write;
+
+/// The size of a pit cell in pixels.
+const CELL: f32 = 60.0;
+/// The margin around the board for the labels.
+const MARGIN: f32 = 40.0;
+/// The gap between the halves of the two players.
+const GAP: f32 = 12.0;
+/// The largest number of seeds drawn as single seeds.
+const MAX_DRAWN_SEEDS: u8 = 16;
+
+/// Draws positions as SVG diagrams.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SvgRenderer {
+    /// Whether to draw small pits as single seeds instead of seed counts.
+    pub draw_seeds: bool,
+    /// Whether to draw an arrow for the move.
+    pub move_arrow: bool,
+    /// Whether to draw the sowing path and captures of the move.
+    pub sowing_path: bool,
+}
+
+impl Default for SvgRenderer {
+    fn default() -> Self {
+        SvgRenderer {
+            draw_seeds: false,
+            move_arrow: true,
+            sowing_path: true,
+        }
+    }
+}
+
+impl SvgRenderer {
+    /// Draws a position.
+    ///
+    /// # Arguments
+    ///
+    /// * `game` - The position, drawn with the player to move at the bottom.
+    /// * `m` - A legal move of the position to draw, if any.
+    ///
+    /// # Returns
+    ///
+    /// The SVG document.
+    pub fn render(&self, game: &Game, m: Option<&Move>) -> String {
+        let width = 2.0 * MARGIN + 8.0 * CELL;
+        let height = 2.0 * MARGIN + 4.0 * CELL + GAP;
+        let mut svg = String::new();
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-family="sans-serif">"#,
+            w = width,
+            h = height
+        );
+        svg.push_str(concat!(
+            r#"<defs><marker id="arrow" viewBox="0 0 10 10" refX="8" refY="5" "#,
+            r#"markerWidth="6" markerHeight="6" orient="auto-start-reverse">"#,
+            r#"<path d="M 0 0 L 10 5 L 0 10 z" fill="context-stroke"/></marker></defs>"#,
+            "\n"
+        ));
+        let _ = writeln!(
+            svg,
+            r##"<rect x="0" y="0" width="{}" height="{}" fill="#f4e3c1"/>"##,
+            width, height
+        );
+        let _ = writeln!(
+            svg,
+            r##"<rect x="{}" y="{}" width="{}" height="{}" rx="8" fill="#b5793d" stroke="#6b4321" stroke-width="2"/>"##,
+            MARGIN - 4.0,
+            MARGIN - 4.0,
+            8.0 * CELL + 8.0,
+            4.0 * CELL + GAP + 8.0
+        );
+
+        // Column labels and the seeds in hand.
+        for col in 0..8 {
+            let x = MARGIN + (col as f32 + 0.5) * CELL;
+            let _ = writeln!(
+                svg,
+                r#"<text x="{}" y="{}" font-size="12" text-anchor="middle">{}</text>"#,
+                x,
+                MARGIN - 10.0,
+                8 - col
+            );
+            let _ = writeln!(
+                svg,
+                r#"<text x="{}" y="{}" font-size="12" text-anchor="middle">{}</text>"#,
+                x,
+                height - MARGIN + 20.0,
+                col + 1
+            );
+        }
+        self.hand(&mut svg, &game.other_player, "Opponent", MARGIN - 24.0);
+        self.hand(&mut svg, &game.current_player, "To move", height - 6.0);
+
+        for top in [true, false] {
+            let player = match top {
+                true => &game.other_player,
+                false => &game.current_player,
+            };
+            for index in 0..16 {
+                self.pit(&mut svg, player, index, top);
+            }
+        }
+
+        if let Some(m) = m {
+            let trace = game.clone().take_turn_traced(m);
+            if self.sowing_path {
+                self.path(&mut svg, &trace);
+            }
+            if self.move_arrow {
+                self.move_arrow(&mut svg, &trace);
+            }
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    fn hand(&self, svg: &mut String, player: &Player, name: &str, y: f32) {
+        let nyumba = match player.nyumba {
+            true => "nyumba standing",
+            false => "no nyumba",
+        };
+        let _ = writeln!(
+            svg,
+            r#"<text x="{}" y="{}" font-size="13">{}: {} seeds in hand, {}</text>"#,
+            MARGIN, y, name, player.seeds, nyumba
+        );
+    }
+
+    fn pit(&self, svg: &mut String, player: &Player, index: i8, top: bool) {
+        let (x, y) = center(index, top);
+        let r = CELL * 0.4;
+        if index == 4 && player.nyumba {
+            // The nyumba is drawn square while it stands.
+            let _ = writeln!(
+                svg,
+                r##"<rect x="{}" y="{}" width="{}" height="{}" rx="4" fill="#7a4a22" stroke="#f4e3c1" stroke-width="3"/>"##,
+                x - r,
+                y - r,
+                2.0 * r,
+                2.0 * r
+            );
+        } else {
+            let _ = writeln!(
+                svg,
+                r##"<circle cx="{}" cy="{}" r="{}" fill="#7a4a22"/>"##,
+                x, y, r
+            );
+        }
+
+        let seeds = player.mashumo.get_seeds(index);
+        if self.draw_seeds && seeds <= MAX_DRAWN_SEEDS {
+            let per_row = (seeds as f32).sqrt().ceil().max(1.0) as u8;
+            let spacing = 2.0 * r / (per_row as f32 + 1.0);
+            for seed in 0..seeds {
+                let (row, col) = (seed / per_row, seed % per_row);
+                let rows = seeds.div_ceil(per_row);
+                let sx = x + (col as f32 - (per_row - 1) as f32 / 2.0) * spacing;
+                let sy = y + (row as f32 - (rows - 1) as f32 / 2.0) * spacing;
+                let _ = writeln!(
+                    svg,
+                    r##"<circle cx="{}" cy="{}" r="{}" fill="#e8e0d0" stroke="#555" stroke-width="0.5"/>"##,
+                    sx,
+                    sy,
+                    (spacing * 0.4).min(5.0)
+                );
+            }
+        } else {
+            let _ = writeln!(
+                svg,
+                r##"<text x="{}" y="{}" font-size="20" text-anchor="middle" fill="#fff">{}</text>"##,
+                x,
+                y + 7.0,
+                seeds
+            );
+        }
+    }
+
+    /// Draws every sowing lap as a path and every capture as a dashed arrow
+    /// from the captured pit to its kichwa.
+    fn path(&self, svg: &mut String, trace: &TurnTrace) {
+        for lap in &trace.laps {
+            let color = match lap.is_capture {
+                true => "#c0392b",
+                false => "#2471a3",
+            };
+            let mut points = vec![center(lap.start, false)];
+            points.extend(
+                lap.pits
+                    .iter()
+                    .skip_while(|&&index| index == lap.start)
+                    .map(|&index| center(index, false)),
+            );
+            if points.len() < 2 {
+                continue;
+            }
+            let points: Vec<String> = points.iter().map(|(x, y)| format!("{},{}", x, y)).collect();
+            let _ = writeln!(
+                svg,
+                r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="3" stroke-opacity="0.8" marker-end="url(#arrow)"/>"#,
+                points.join(" "),
+                color
+            );
+        }
+        for capture in &trace.captures {
+            let (x1, y1) = center(capture.index, true);
+            let (x2, y2) = center(capture.kichwa, false);
+            let _ = writeln!(
+                svg,
+                r##"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="#c0392b" stroke-width="3" stroke-dasharray="6 4" marker-end="url(#arrow)"/>"##,
+                x1, y1, x2, y2
+            );
+        }
+    }
+
+    /// Draws an arrow from the pit of the move into its direction.
+    fn move_arrow(&self, svg: &mut String, trace: &TurnTrace) {
+        let from = trace.m.index;
+        let (to, to_top) = match trace.laps.first() {
+            // Namua captures point at the captured pit.
+            Some(lap) if lap.is_capture => (7 - from, true),
+            Some(lap) => match lap.pits.first() {
+                Some(&index) => (index, false),
+                None => return,
+            },
+            None => return,
+        };
+        let (x1, y1) = center(from, false);
+        let (x2, y2) = center(to, to_top);
+        let _ = writeln!(
+            svg,
+            r##"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="#f1c40f" stroke-width="5" marker-end="url(#arrow)"/>"##,
+            x1, y1, x2, y2
+        );
+    }
+}
+
+/// Returns the centre of a pit. The rows and columns are laid out like the
+/// terminal board.
+fn center(index: i8, top: bool) -> (f32, f32) {
+    let (row, col) = match (top, index) {
+        (true, 8..=15) => (0, index - 8),
+        (true, _) => (1, 7 - index),
+        (false, 0..=7) => (2, index),
+        (false, _) => (3, 15 - index),
+    };
+    let gap = if row >= 2 { GAP } else { 0.0 };
+    (
+        MARGIN + (col as f32 + 0.5) * CELL,
+        MARGIN + (row as f32 + 0.5) * CELL + gap,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::bao::game_builder::GameBuilder;
+
+    #[test]
+    fn test_render_svg() {
+        let game = Game::new();
+        let svg = SvgRenderer::default().render(&game, None);
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        // Both nyumba are standing and drawn square.
+        assert_eq!(svg.matches("rx=\"4\"").count(), 2);
+        assert!(!svg.contains("<polyline"));
+
+        // The namua capture 3R takes the seeds of A6 of the opponent.
+        let game = bao_game!("6L", "5R");
+        let m = Move::namua_capture_right(2);
+        let svg = SvgRenderer::default().render(&game, Some(&m));
+        // The capture lap from the right kichwa and the relay from A7.
+        assert_eq!(svg.matches("<polyline").count(), 2);
+        assert_eq!(svg.matches("stroke-dasharray").count(), 1);
+        // The move arrow points from A3 at the captured pit of the opponent.
+        let (x1, y1) = center(2, false);
+        let (x2, y2) = center(5, true);
+        assert!(svg.contains(&format!(
+            r##"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="#f1c40f""##,
+            x1, y1, x2, y2
+        )));
+    }
+}
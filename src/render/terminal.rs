@@ -0,0 +1,261 @@
+//! Terminal board rendering with optional ANSI colours.
+//!
+//! Colours mark the nyumba pits, the pits that changed during the last turn,
+//! the pits seeds were captured from and the active pit of an animation.
+//! Without colour the board is plain text, and only the active pit is marked
+//! with a `*`.
+
+use crate::bao::events::TurnTrace;
+use crate::bao::game::{self, Game};
+use crate::bao::player::Player;
+use std::io::IsTerminal;
+
+/// How a pit is marked.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum PitMark {
+    #[default]
+    None,
+    /// The pit changed during the last turn.
+    Changed,
+    /// Seeds were captured from the pit during the last turn.
+    Captured,
+    /// The pit of the current step of an animated turn.
+    Active,
+}
+
+/// The marks of all pits on the board, for the bottom and the top player.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BoardMarks {
+    marks: [[PitMark; 16]; 2],
+}
+
+impl BoardMarks {
+    /// Returns the marks with a single active pit.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index of the pit.
+    /// * `top` - Whether the pit belongs to the top player.
+    pub fn active(index: i8, top: bool) -> BoardMarks {
+        let mut marks = BoardMarks::default();
+        marks.set(index, top, PitMark::Active);
+        marks
+    }
+
+    /// Returns the marks of the pits that changed during a turn.
+    ///
+    /// # Arguments
+    ///
+    /// * `before` - The position before the turn.
+    /// * `after` - The position after the turn.
+    /// * `trace` - The trace of the turn.
+    /// * `mover_is_top` - Whether the player who took the turn is shown at the top.
+    pub fn last_turn(
+        before: &Game,
+        after: &Game,
+        trace: &TurnTrace,
+        mover_is_top: bool,
+    ) -> BoardMarks {
+        let mut marks = BoardMarks::default();
+        // The players swapped after the turn.
+        let sides = [
+            (&before.current_player, &after.other_player, mover_is_top),
+            (&before.other_player, &after.current_player, !mover_is_top),
+        ];
+        for (old, new, top) in sides {
+            for index in 0..16 {
+                if old.mashumo.get_seeds(index) != new.mashumo.get_seeds(index) {
+                    marks.set(index, top, PitMark::Changed);
+                }
+            }
+        }
+        for capture in &trace.captures {
+            marks.set(capture.index, !mover_is_top, PitMark::Captured);
+        }
+        marks
+    }
+
+    pub fn set(&mut self, index: i8, top: bool, mark: PitMark) {
+        self.marks[top as usize][index as usize] = mark;
+    }
+
+    pub fn get(&self, index: i8, top: bool) -> PitMark {
+        self.marks[top as usize][index as usize]
+    }
+}
+
+/// Renders boards as text for the terminal.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BoardRenderer {
+    /// Whether to use ANSI colours.
+    pub color: bool,
+    /// Whether to leave empty pits blank unless they are marked.
+    pub hide_zeros: bool,
+}
+
+impl BoardRenderer {
+    /// Returns a renderer that uses colour if stdout is a terminal and
+    /// `NO_COLOR` is not set.
+    pub fn new() -> BoardRenderer {
+        BoardRenderer {
+            color: use_color(),
+            hide_zeros: false,
+        }
+    }
+
+    /// Returns a renderer without colour.
+    pub fn plain() -> BoardRenderer {
+        BoardRenderer::default()
+    }
+
+    /// Returns the renderer with empty pits left blank or not.
+    pub fn with_hide_zeros(mut self, hide_zeros: bool) -> BoardRenderer {
+        self.hide_zeros = hide_zeros;
+        self
+    }
+
+    /// Renders a game with the player to move at the bottom.
+    pub fn render_game(&self, game: &Game) -> String {
+        self.render(
+            &game.current_player,
+            &game.other_player,
+            "Player 1",
+            "Player 2",
+            &BoardMarks::default(),
+        )
+    }
+
+    /// Renders a board with one player at the bottom and the other at the top.
+    ///
+    /// # Arguments
+    ///
+    /// * `bottom` - The player shown at the bottom, whose pits are labelled.
+    /// * `top` - The player shown at the top.
+    /// * `bottom_name` - The name of the bottom player.
+    /// * `top_name` - The name of the top player.
+    /// * `marks` - The marks of the pits.
+    pub fn render(
+        &self,
+        bottom: &Player,
+        top: &Player,
+        bottom_name: &str,
+        top_name: &str,
+        marks: &BoardMarks,
+    ) -> String {
+        game::format_board_with(
+            bottom,
+            top,
+            bottom_name,
+            top_name,
+            |player, index, is_top| self.pit(player, index, marks.get(index, is_top)),
+        )
+    }
+
+    /// Renders a single pit followed by a space.
+    fn pit(&self, player: &Player, index: i8, mark: PitMark) -> String {
+        let seeds = player.mashumo.get_seeds(index);
+        // Marked pits show their zeros, so emptied pits stay visible.
+        let text = match (seeds, self.hide_zeros) {
+            (0, true) if mark == PitMark::None => " ".to_string(),
+            _ => seeds.to_string(),
+        };
+        if !self.color {
+            return match mark {
+                PitMark::Active => format!("{}*", text),
+                _ => format!("{} ", text),
+            };
+        }
+
+        let is_nyumba = index == 4 && player.nyumba;
+        let mut codes = vec![];
+        match mark {
+            PitMark::None if is_nyumba => codes.push("36"),
+            PitMark::None => {}
+            PitMark::Changed => codes.push("1;32"),
+            PitMark::Captured => codes.push("1;31"),
+            PitMark::Active => codes.push("7"),
+        }
+        if is_nyumba {
+            codes.push("4");
+        }
+        match codes.is_empty() {
+            true => format!("{} ", text),
+            false => format!("\x1B[{}m{}\x1B[0m ", codes.join(";"), text),
+        }
+    }
+}
+
+/// Returns whether stdout is a terminal and `NO_COLOR` is not set.
+pub fn use_color() -> bool {
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    std::io::stdout().is_terminal() && !no_color
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::bao::game_builder::GameBuilder;
+    use crate::bao::moves::Move;
+
+    #[test]
+    fn test_render_plain_matches_display() {
+        let game = Game::new();
+        let board = BoardRenderer::plain().render_game(&game);
+        assert_eq!(board, game.to_string());
+        assert!(board.contains("  L | 0 0 0 0 6 2 2 0 | R  \n"));
+        assert!(!board.contains('\x1B'));
+
+        let board = BoardRenderer::plain().with_hide_zeros(true).render(
+            &game.current_player,
+            &game.other_player,
+            "South",
+            "North",
+            &BoardMarks::active(5, false),
+        );
+        assert!(board.contains("  L |         6 2*2   | R  \n"));
+    }
+
+    #[test]
+    fn test_render_nyumba_while_it_stands() {
+        let renderer = BoardRenderer {
+            color: true,
+            hide_zeros: false,
+        };
+        let mut game = Game::new();
+        assert_eq!(
+            renderer.render_game(&game).matches("\x1B[36;4m6").count(),
+            2
+        );
+        game.current_player.nyumba = false;
+        let board = renderer.render_game(&game);
+        assert_eq!(board.matches("\x1B[36;4m6").count(), 1);
+        assert!(board.contains("  L | 0 0 0 0 6 2 2 0 | R  \n"));
+    }
+
+    #[test]
+    fn test_render_last_turn() {
+        // The namua capture 3R, with the player who takes it shown at the top.
+        let before = bao_game!("6L", "5R");
+        let mut after = before.clone();
+        let trace = after.take_turn_traced(&Move::namua_capture_right(2));
+        let marks = BoardMarks::last_turn(&before, &after, &trace, true);
+        // The captured seeds are sown from the right kichwa A8.
+        assert_eq!(marks.get(7, true), PitMark::Changed);
+        assert_eq!(marks.get(5, false), PitMark::Captured);
+        assert_eq!(marks.get(4, false), PitMark::None);
+
+        let renderer = BoardRenderer {
+            color: true,
+            hide_zeros: false,
+        };
+        let board = renderer.render(
+            &after.current_player,
+            &after.other_player,
+            "South",
+            "North",
+            &marks,
+        );
+        assert!(board.contains("  L | 0 0 0 0 \x1B[36;4m7\x1B[0m \x1B[1;31m0\x1B[0m "));
+    }
+}
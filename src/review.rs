@@ -0,0 +1,205 @@
+//! Review of whole games with move annotations.
+
+use crate::bao::moves::Move;
+use crate::bao::record::GameRecord;
+use crate::config::SearchConfig;
+use crate::error::{ReviewError, ReviewResult};
+use crate::search::analyze::{self, MoveAnalysis};
+use serde::Serialize;
+use std::fmt::{Display, Formatter, Result};
+
+/// The quality of a move, judged by how much worse it is than the best move.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Annotation {
+    Best,
+    Good,
+    Inaccuracy,
+    Mistake,
+    Blunder,
+}
+
+impl Annotation {
+    /// Returns the usual symbol of the annotation.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Annotation::Best => "!",
+            Annotation::Good => "",
+            Annotation::Inaccuracy => "?!",
+            Annotation::Mistake => "?",
+            Annotation::Blunder => "??",
+        }
+    }
+}
+
+impl Display for Annotation {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        let name = match self {
+            Annotation::Best => "best",
+            Annotation::Good => "good",
+            Annotation::Inaccuracy => "inaccuracy",
+            Annotation::Mistake => "mistake",
+            Annotation::Blunder => "blunder",
+        };
+        f.pad(name)
+    }
+}
+
+/// The largest evaluation drops of each annotation. Larger drops are blunders.
+#[derive(Clone, Debug)]
+pub struct ReviewThresholds {
+    pub good: f32,
+    pub inaccuracy: f32,
+    pub mistake: f32,
+}
+
+impl Default for ReviewThresholds {
+    fn default() -> Self {
+        ReviewThresholds {
+            good: 0.5,
+            inaccuracy: 1.5,
+            mistake: 3.0,
+        }
+    }
+}
+
+impl ReviewThresholds {
+    /// Returns the annotation of a move that is `loss` worse than the best move.
+    pub fn annotate(&self, loss: f32) -> Annotation {
+        match loss {
+            l if l <= 0.0 => Annotation::Best,
+            l if l <= self.good => Annotation::Good,
+            l if l <= self.inaccuracy => Annotation::Inaccuracy,
+            l if l <= self.mistake => Annotation::Mistake,
+            _ => Annotation::Blunder,
+        }
+    }
+}
+
+/// A reviewed move of the game.
+#[derive(Clone, Debug, Serialize)]
+pub struct ReviewedMove {
+    pub m: Move,
+    /// The analysis of the played move.
+    pub played: MoveAnalysis,
+    /// The analysis of the best move.
+    pub best: MoveAnalysis,
+    /// How much worse the played move is than the best move.
+    pub loss: f32,
+    pub annotation: Annotation,
+}
+
+impl ReviewedMove {
+    /// Returns the accuracy of the move in percent. It falls off
+    /// exponentially with the evaluation drop.
+    pub fn accuracy(&self) -> f64 {
+        100.0 * (-self.loss as f64 / 3.0).exp()
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Review {
+    pub moves: Vec<ReviewedMove>,
+}
+
+impl Review {
+    /// Returns the mean accuracy of the first (0) or second (1) player.
+    pub fn accuracy(&self, player: usize) -> f64 {
+        let accuracies: Vec<f64> = self
+            .moves
+            .iter()
+            .skip(player)
+            .step_by(2)
+            .map(ReviewedMove::accuracy)
+            .collect();
+        accuracies.iter().sum::<f64>() / accuracies.len().max(1) as f64
+    }
+
+    /// Returns the number of moves of a player with the given annotation.
+    pub fn count(&self, player: usize, annotation: Annotation) -> usize {
+        self.moves
+            .iter()
+            .skip(player)
+            .step_by(2)
+            .filter(|m| m.annotation == annotation)
+            .count()
+    }
+}
+
+/// Searches every position of a game and annotates the played moves.
+///
+/// # Arguments
+///
+/// * `record` - The game to review.
+/// * `config` - The search configuration of every position's analysis.
+/// * `thresholds` - The evaluation drops of the annotations.
+pub fn review(
+    record: &GameRecord,
+    config: &SearchConfig,
+    thresholds: &ReviewThresholds,
+) -> ReviewResult<Review> {
+    let mut review = Review::default();
+    for (game, m) in record.games()?.iter().zip(record.moves.iter()) {
+        let analysis = analyze::analyze(config, game)?;
+        let best = analysis[0].clone();
+        let played = analysis
+            .into_iter()
+            .find(|a| a.m == *m)
+            .expect("Played move is not legal");
+        // A move without a finished depth has no score to compare.
+        if !played.searched || !best.searched {
+            return Err(ReviewError::Unsearched(played.m.to_string()));
+        }
+        let loss = played.loss(&best);
+        review.moves.push(ReviewedMove {
+            m: *m,
+            played,
+            best,
+            loss,
+            annotation: thresholds.annotate(loss),
+        });
+    }
+    Ok(review)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_annotate() {
+        let thresholds = ReviewThresholds::default();
+        assert_eq!(thresholds.annotate(0.0), Annotation::Best);
+        assert_eq!(thresholds.annotate(0.5), Annotation::Good);
+        assert_eq!(thresholds.annotate(1.0), Annotation::Inaccuracy);
+        assert_eq!(thresholds.annotate(2.0), Annotation::Mistake);
+        assert_eq!(thresholds.annotate(f32::INFINITY), Annotation::Blunder);
+    }
+
+    #[test]
+    fn test_review() -> ReviewResult<()> {
+        let record = GameRecord::from_moves_str("6L 5R; 3L")?;
+        let config = SearchConfig::new(2, 1, None);
+        let review = review(&record, &config, &ReviewThresholds::default())?;
+        assert_eq!(review.moves.len(), 3);
+        for reviewed in &review.moves {
+            assert!(reviewed.loss >= 0.0);
+            assert_eq!(reviewed.best.rank, 1);
+            assert!(reviewed.accuracy() <= 100.0);
+        }
+        assert!(review.accuracy(0) > 0.0);
+        let num_first: usize = [
+            Annotation::Best,
+            Annotation::Good,
+            Annotation::Inaccuracy,
+            Annotation::Mistake,
+            Annotation::Blunder,
+        ]
+        .iter()
+        .map(|&a| review.count(0, a))
+        .sum();
+        assert_eq!(num_first, 2);
+        Ok(())
+    }
+}
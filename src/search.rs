@@ -1,8 +1,19 @@
 //! Search algorithm implementation and related modules.
+pub mod analyze;
+pub mod book;
+pub mod eval_params;
 mod evaluate;
+pub mod mcts;
 mod move_picker;
 pub mod negamax;
 mod node;
+mod quiescence;
+pub mod searcher;
+pub mod solver;
+pub mod strength;
+pub mod tablebase;
 mod timer;
+pub mod trace;
 mod transposition_table;
-mod zobrist;
+pub mod tune;
+pub mod zobrist;
@@ -0,0 +1,165 @@
+//! Analysis of every legal root move.
+
+use crate::bao::game::Game;
+use crate::bao::move_factory::MoveFactory;
+use crate::bao::moves::Move;
+use crate::bao::pv::PVLine;
+use crate::config::SearchConfig;
+use crate::error::SearchResult;
+use crate::search::eval_params::EvalParams;
+use crate::search::evaluate;
+use crate::search::move_picker::MovePicker;
+use crate::search::negamax;
+use crate::search::node::Node;
+use crate::search::searcher;
+use serde::Serialize;
+
+/// The analysis of a root move.
+#[derive(Clone, Debug, Serialize)]
+pub struct MoveAnalysis {
+    pub m: Move,
+    /// The score of the move for the player to move.
+    pub score: f32,
+    /// The principal variation starting with the move.
+    pub pv: PVLine,
+    /// The rank of the move, starting at 1. Moves with equal scores share a rank.
+    /// Unsearched moves have rank 0.
+    pub rank: usize,
+    /// Whether the search of the move finished a depth. If not, the score and
+    /// the principal variation mean nothing.
+    pub searched: bool,
+}
+
+impl MoveAnalysis {
+    /// Returns how much worse the move is than the best one.
+    pub fn loss(&self, best: &MoveAnalysis) -> f32 {
+        match (best.score.is_infinite(), self.score == best.score) {
+            (_, true) => 0.0,
+            (true, false) => f32::INFINITY,
+            (false, false) => best.score - self.score,
+        }
+    }
+}
+
+/// Returns the static evaluation of a position for the player to move, the
+/// score of a search at depth zero.
+///
+/// # Arguments
+///
+/// * `game` - The position to evaluate.
+/// * `params` - The evaluation parameters.
+pub fn static_evaluation(game: &Game, params: &EvalParams) -> f32 {
+    let mut picker = MovePicker::new();
+    let legal_moves = picker.pick_moves_unordered(game);
+    if legal_moves.is_empty() || game.current_player.mashumo.bitboard == 0 {
+        f32::NEG_INFINITY
+    } else if game.other_player.mashumo.bitboard == 0 {
+        f32::INFINITY
+    } else {
+        evaluate::evaluate(Node::new(game.clone()), legal_moves, params)
+    }
+}
+
+/// Searches every legal move of a position with a full window.
+///
+/// # Arguments
+///
+/// * `config` - The search configuration of every move's search.
+/// * `game` - The position to analyze.
+///
+/// # Returns
+///
+/// The analysis of all legal moves, sorted from best to worst. Moves whose
+/// search ran out of time before finishing a depth come last.
+pub fn analyze(config: &SearchConfig, game: &Game) -> SearchResult<Vec<MoveAnalysis>> {
+    // Every move has to be searched, so don't play from the book.
+    let mut config = config.clone();
+    config.book = None;
+
+    let mut factory = MoveFactory::new(game);
+    let mut analysis = vec![];
+    for m in factory.get_legal_moves().to_vec() {
+        let mut child = game.clone();
+        child.take_turn(&m);
+        let (reply, searched) = match searcher::probe(&config, &child, false) {
+            Some(reply) => (reply, true),
+            None => {
+                let mut searched = false;
+                let reply = negamax::search_with_observer(config.clone(), child, |_| {
+                    searched = true;
+                })?;
+                (reply, searched)
+            }
+        };
+        let mut pv = PVLine::default();
+        pv.update(Some(m), reply.clone(), -reply.value);
+        analysis.push(MoveAnalysis {
+            m,
+            score: pv.value,
+            pv,
+            rank: 0,
+            searched,
+        });
+    }
+
+    analysis.sort_by(|a, b| {
+        b.searched
+            .cmp(&a.searched)
+            .then(b.score.total_cmp(&a.score))
+    });
+    for i in 0..analysis.len() {
+        analysis[i].rank = match (analysis[i].searched, i) {
+            (false, _) => 0,
+            (true, 0) => 1,
+            (true, _) if analysis[i].score == analysis[i - 1].score => analysis[i - 1].rank,
+            (true, _) => i + 1,
+        };
+    }
+    Ok(analysis)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::bao::notation;
+
+    #[test]
+    fn test_analyze_ranks_winning_capture_first() -> SearchResult<()> {
+        let game = notation::from_notation(
+            "0,1,0,0,0,0,0,0,0,0,0,0,0,0,3,0/0,0,0,0,0,0,2,0,2,0,0,0,0,0,0,0 0/0 00",
+        )
+        .unwrap();
+        let analysis = analyze(&SearchConfig::new(3, 1, None), &game)?;
+        assert_eq!(analysis.len(), 1);
+        assert_eq!(analysis[0].m, Move::mtaji_capture_right(14));
+        assert_eq!(analysis[0].score, f32::INFINITY);
+        assert_eq!(analysis[0].rank, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_analyze_starting_position() -> SearchResult<()> {
+        let game = Game::new();
+        let analysis = analyze(&SearchConfig::new(3, 1, None), &game)?;
+        let num_moves = MoveFactory::new(&game).get_legal_moves().len();
+        assert_eq!(analysis.len(), num_moves);
+        for (a, b) in analysis.iter().zip(analysis.iter().skip(1)) {
+            assert!(a.score >= b.score);
+            assert!(a.rank <= b.rank);
+            assert!(b.loss(&analysis[0]) >= 0.0);
+        }
+        assert!(analysis.iter().all(|a| a.pv.moves[0] == a.m));
+        Ok(())
+    }
+
+    #[test]
+    fn test_analyze_out_of_nodes_leaves_moves_unsearched() -> SearchResult<()> {
+        let mut config = SearchConfig::new(20, 1, None);
+        config.max_nodes = Some(1);
+        let analysis = analyze(&config, &Game::new())?;
+        assert!(!analysis.is_empty());
+        assert!(analysis.iter().all(|a| !a.searched && a.rank == 0));
+        Ok(())
+    }
+}
@@ -0,0 +1,382 @@
+//! Opening book.
+//!
+//! The book maps stable position hashes to the moves played from them. It is
+//! built from deep searches or from game records and stored in a compact
+//! binary file: an 8 byte header followed by one 26 byte entry per move,
+//! holding the position hash, the move, its weight and the wins, draws and
+//! losses of the player to move.
+
+use crate::bao::game::Game;
+use crate::bao::move_factory::MoveFactory;
+use crate::bao::moves::{flags, Move};
+use crate::bao::player::Player;
+use crate::bao::record::{GameRecord, GameResult};
+use crate::config::SearchConfig;
+use crate::error::{BookError, BookResult, SearchResult};
+use crate::search::negamax;
+use rand::distributions::{Distribution, WeightedIndex};
+use rustc_hash::FxHashMap;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+const MAGIC: &[u8; 8] = b"KIKBOOK1";
+const ENTRY_SIZE: usize = 26;
+
+/// A move stored in the book.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BookEntry {
+    pub m: Move,
+    /// The relative probability of playing the move.
+    pub weight: u32,
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl BookEntry {
+    fn new(m: Move) -> BookEntry {
+        BookEntry {
+            m,
+            weight: 0,
+            wins: 0,
+            draws: 0,
+            losses: 0,
+        }
+    }
+
+    /// Returns the number of games in which the move was played.
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Book {
+    positions: BTreeMap<u64, Vec<BookEntry>>,
+}
+
+impl Book {
+    pub fn new() -> Book {
+        Book::default()
+    }
+
+    /// Returns the number of positions in the book.
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// Returns the number of moves in the book.
+    pub fn num_entries(&self) -> usize {
+        self.positions.values().map(Vec::len).sum()
+    }
+
+    /// Returns the book moves of a position.
+    pub fn entries(&self, game: &Game) -> &[BookEntry] {
+        self.positions
+            .get(&position_hash(game))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    fn entry(&mut self, game: &Game, m: Move) -> &mut BookEntry {
+        let entries = self.positions.entry(position_hash(game)).or_default();
+        let i = match entries.iter().position(|e| e.m == m) {
+            Some(i) => i,
+            None => {
+                entries.push(BookEntry::new(m));
+                entries.len() - 1
+            }
+        };
+        &mut entries[i]
+    }
+
+    /// Picks a legal book move at random, weighted by the move weights.
+    ///
+    /// # Returns
+    ///
+    /// None if the position is not in the book or all its moves have zero weight.
+    pub fn probe(&self, game: &Game) -> Option<Move> {
+        let mut factory = MoveFactory::new(game);
+        let legal_moves = factory.get_legal_moves();
+        let entries: Vec<&BookEntry> = self
+            .entries(game)
+            .iter()
+            .filter(|e| e.weight > 0 && legal_moves.contains(&e.m))
+            .collect();
+        let dist = WeightedIndex::new(entries.iter().map(|e| e.weight)).ok()?;
+        Some(entries[dist.sample(&mut rand::thread_rng())].m)
+    }
+
+    /// Adds the moves of a decided game up to a number of plies. Every move
+    /// is weighted by the points it scored, two for a win and one for a draw.
+    pub fn add_record(&mut self, record: &GameRecord, max_plies: usize) -> BookResult<()> {
+        let first_score = match record.result {
+            GameResult::FirstPlayerWins => 2,
+            GameResult::SecondPlayerWins => 0,
+            GameResult::Draw => 1,
+            GameResult::Unknown => return Ok(()),
+        };
+        let games = record.games()?;
+        for (ply, (game, m)) in games.iter().zip(record.moves.iter()).enumerate() {
+            if ply >= max_plies {
+                break;
+            }
+            let entry = self.entry(game, *m);
+            match (first_score, ply % 2) {
+                (1, _) => entry.draws += 1,
+                (2, 0) | (0, 1) => entry.wins += 1,
+                _ => entry.losses += 1,
+            }
+            entry.weight = 2 * entry.wins + entry.draws;
+        }
+        Ok(())
+    }
+
+    /// Builds a book by searching every position up to a number of plies
+    /// from the starting position and storing the best move of each.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The search configuration of the deep searches.
+    /// * `max_plies` - The number of plies to expand.
+    pub fn from_search(config: &SearchConfig, max_plies: u8) -> SearchResult<Book> {
+        let mut book = Book::new();
+        let mut visited = FxHashMap::default();
+        let mut config = config.clone();
+        config.book = None;
+        book.expand(&Game::new(), &config, max_plies, &mut visited)?;
+        Ok(book)
+    }
+
+    /// Searches a position and expands its children.
+    ///
+    /// # Arguments
+    ///
+    /// * `game` - The position to expand.
+    /// * `config` - The search configuration of the deep searches.
+    /// * `plies` - The number of plies left to expand.
+    /// * `visited` - The most plies left any expansion of a position had. A
+    ///   position reached again at a shallower ply is expanded deeper, but
+    ///   searched only once.
+    fn expand(
+        &mut self,
+        game: &Game,
+        config: &SearchConfig,
+        plies: u8,
+        visited: &mut FxHashMap<u64, u8>,
+    ) -> SearchResult<()> {
+        if plies == 0 || game.is_over() {
+            return Ok(());
+        }
+        let hash = position_hash(game);
+        if visited.get(&hash).is_some_and(|&seen| seen >= plies) {
+            return Ok(());
+        }
+        if visited.insert(hash, plies).is_none() {
+            let pvline = negamax::search(config.clone(), game.clone(), false)?;
+            if let Some(m) = pvline.moves.first() {
+                self.entry(game, *m).weight += 1;
+            }
+        }
+        let mut factory = MoveFactory::new(game);
+        for m in factory.get_legal_moves().to_vec() {
+            let mut child = game.clone();
+            child.take_turn(&m);
+            self.expand(&child, config, plies - 1, visited)?;
+        }
+        Ok(())
+    }
+
+    /// Loads a book from a binary file.
+    pub fn load<P: AsRef<Path>>(path: P) -> BookResult<Book> {
+        Book::from_bytes(&fs::read(path)?)
+    }
+
+    /// Saves the book to a binary file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> BookResult<()> {
+        fs::write(path, self.to_bytes())?;
+        Ok(())
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(MAGIC.len() + ENTRY_SIZE * self.num_entries());
+        bytes.extend_from_slice(MAGIC);
+        for (hash, entries) in &self.positions {
+            for e in entries {
+                bytes.extend_from_slice(&hash.to_le_bytes());
+                bytes.push(e.m.index as u8);
+                bytes.push(e.m.flags);
+                for value in [e.weight, e.wins, e.draws, e.losses] {
+                    bytes.extend_from_slice(&value.to_le_bytes());
+                }
+            }
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> BookResult<Book> {
+        if !bytes.starts_with(MAGIC) {
+            return Err(BookError::InvalidHeader);
+        }
+        let body = &bytes[MAGIC.len()..];
+        if !body.len().is_multiple_of(ENTRY_SIZE) {
+            return Err(BookError::InvalidLength(bytes.len()));
+        }
+        let mut book = Book::new();
+        for chunk in body.chunks_exact(ENTRY_SIZE) {
+            let hash = u64::from_le_bytes(chunk[0..8].try_into().expect("8 bytes"));
+            let value = |i: usize| u32::from_le_bytes(chunk[i..i + 4].try_into().expect("4 bytes"));
+            book.positions.entry(hash).or_default().push(BookEntry {
+                m: decode_move(chunk[8], chunk[9])?,
+                weight: value(10),
+                wins: value(14),
+                draws: value(18),
+                losses: value(22),
+            });
+        }
+        Ok(book)
+    }
+}
+
+/// Decodes a stored move. Book moves start a turn, so they are never relays,
+/// and namua moves are played from the front row.
+fn decode_move(index: u8, move_flags: u8) -> BookResult<Move> {
+    let known = flags::DIRECTION_R | flags::NAMUA | flags::CAPTURE;
+    let max_index = match move_flags & flags::NAMUA {
+        0 => 15,
+        _ => 7,
+    };
+    if index > max_index || move_flags & !known != 0 {
+        return Err(BookError::InvalidMove(index, move_flags));
+    }
+    Ok(Move::new(index as i8, move_flags))
+}
+
+/// Returns a hash of the position that, unlike the zobrist hash of the
+/// search, does not depend on the move order or the process. It is the
+/// 64 bit FNV-1a hash of both players' pits, seeds in hand and nyumba.
+pub fn position_hash(game: &Game) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    let mut write = |byte: u8| {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    };
+    for player in [&game.current_player, &game.other_player] {
+        write_player(player, &mut write);
+    }
+    hash
+}
+
+fn write_player(player: &Player, write: &mut impl FnMut(u8)) {
+    for seeds in player.mashumo.mashumo {
+        write(seeds);
+    }
+    write(player.seeds);
+    write(player.nyumba as u8);
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::bao::game_builder::GameBuilder;
+    use crate::bao::notation;
+
+    #[test]
+    fn test_position_hash() {
+        let game = crate::bao_game!("6L", "5R");
+        let copy = notation::from_notation(&notation::to_notation(&game)).unwrap();
+        assert_eq!(position_hash(&game), position_hash(&copy));
+        assert_ne!(position_hash(&game), position_hash(&Game::new()));
+        assert_eq!(position_hash(&Game::new()), 0x2c43_314e_79e4_94d1);
+    }
+
+    #[test]
+    fn test_add_record_and_probe() -> BookResult<()> {
+        let mut book = Book::new();
+        let mut record = GameRecord::from_moves_str("6L 5R; 3L")?;
+        record.result = GameResult::FirstPlayerWins;
+        book.add_record(&record, 2)?;
+        record.result = GameResult::Draw;
+        book.add_record(&record, 2)?;
+        assert_eq!(book.len(), 2);
+
+        let entries = book.entries(&Game::new());
+        assert_eq!(entries.len(), 1);
+        assert_eq!(
+            (entries[0].wins, entries[0].draws, entries[0].weight),
+            (1, 1, 3)
+        );
+        assert_eq!(book.probe(&Game::new()), Some(entries[0].m));
+
+        // The second player never won with its move.
+        let game = crate::bao_game!("6L");
+        assert_eq!(book.entries(&game)[0].weight, 1);
+        assert_eq!(book.probe(&crate::bao_game!("6L", "5R")), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_expand_again_from_shallower_ply() -> SearchResult<()> {
+        let config = SearchConfig::new(1, 1, None);
+        let mut book = Book::new();
+        let mut visited = FxHashMap::default();
+        let child = crate::bao_game!("6L");
+        let grandchild = crate::bao_game!("6L", "5R");
+
+        // First reached deep, with only the position itself left to search.
+        book.expand(&child, &config, 1, &mut visited)?;
+        assert!(book.entries(&grandchild).is_empty());
+
+        // Reached again from the root, its children are expanded too.
+        book.expand(&Game::new(), &config, 3, &mut visited)?;
+        assert_eq!(book.entries(&child).len(), 1);
+        assert_eq!(book.entries(&child)[0].weight, 1);
+        assert!(!book.entries(&grandchild).is_empty());
+        assert_eq!(visited[&position_hash(&child)], 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_bytes_from_bytes() -> BookResult<()> {
+        let mut book = Book::new();
+        let mut record = GameRecord::from_moves_str("7L 5L; 6R 1")?;
+        record.result = GameResult::SecondPlayerWins;
+        book.add_record(&record, 10)?;
+        let bytes = book.to_bytes();
+        assert_eq!(bytes.len(), 8 + 4 * ENTRY_SIZE);
+
+        let loaded = Book::from_bytes(&bytes)?;
+        assert_eq!(loaded.positions, book.positions);
+        assert!(matches!(
+            Book::from_bytes(&bytes[..20]),
+            Err(BookError::InvalidLength(20))
+        ));
+        assert!(matches!(
+            Book::from_bytes(b"NOTABOOK"),
+            Err(BookError::InvalidHeader)
+        ));
+
+        // Corrupt moves are rejected instead of failing when they are shown.
+        let invalid = [
+            (100, 0),
+            (16, flags::CAPTURE),
+            (12, flags::NAMUA),
+            (3, flags::RELAY),
+        ];
+        for (index, move_flags) in invalid {
+            let mut corrupt = bytes.clone();
+            corrupt[MAGIC.len() + 8] = index;
+            corrupt[MAGIC.len() + 9] = move_flags;
+            assert!(matches!(
+                Book::from_bytes(&corrupt),
+                Err(BookError::InvalidMove(..))
+            ));
+        }
+        Ok(())
+    }
+}
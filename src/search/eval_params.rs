@@ -0,0 +1,193 @@
+//! Evaluation parameters that can be loaded from and saved to a file.
+//!
+//! The file format is a small subset of TOML: one `key = value` pair per line,
+//! where the value is either a number or a list of numbers in brackets. Lines
+//! starting with `#` are comments. Keys that are missing from a file keep their
+//! default value.
+
+use crate::error::{EvalParamsError, EvalParamsResult};
+use crate::search::evaluate::{Feature, FEATURES, NUM_FEATURES};
+use std::fmt::{Display, Formatter, Result};
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Weights of the evaluation function and bonuses for move ordering.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EvalParams {
+    /// Weight of a seed in each of the 16 pits of a player.
+    pub pit_weights: [f32; 16],
+    /// Weight of each evaluation feature.
+    pub feature_weights: [f32; NUM_FEATURES],
+    /// Move ordering bonus per seed in the source pit of a capture.
+    pub capture_seeds_bonus: f32,
+    /// Move ordering bonus for captures that protect the nyumba.
+    pub nyumba_capture_bonus: f32,
+}
+
+impl Default for EvalParams {
+    fn default() -> Self {
+        EvalParams {
+            pit_weights: [
+                1.0, // Kichwa
+                1.0, 1.0, 1.0, // Opponent's nyumba
+                1.0, // Player's nyumba
+                1.0, 1.0, 1.0, // Kichwa
+                1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0,
+            ],
+            feature_weights: [
+                1.0,  // Material
+                0.5,  // Capture mobility
+                0.1,  // Mobility
+                2.0,  // Nyumba
+                0.1,  // Front row
+                -0.5, // Exposure
+                1.0,  // Seeds in hand
+            ],
+            capture_seeds_bonus: 1.0,
+            nyumba_capture_bonus: 2.0,
+        }
+    }
+}
+
+impl EvalParams {
+    /// Returns the weight of an evaluation feature.
+    pub fn feature_weight(&self, feature: Feature) -> f32 {
+        self.feature_weights[feature as usize]
+    }
+
+    /// Loads evaluation parameters from a file.
+    pub fn load<P: AsRef<Path>>(path: P) -> EvalParamsResult<EvalParams> {
+        fs::read_to_string(path)?.parse()
+    }
+
+    /// Saves the evaluation parameters to a file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> EvalParamsResult<()> {
+        fs::write(path, self.to_string())?;
+        Ok(())
+    }
+}
+
+fn parse_value(key: &str, value: &str) -> EvalParamsResult<f32> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| EvalParamsError::InvalidValue(key.to_string(), value.trim().to_string()))
+}
+
+fn parse_list(key: &str, value: &str, expected: usize) -> EvalParamsResult<Vec<f32>> {
+    let list = value
+        .trim()
+        .strip_prefix('[')
+        .and_then(|v| v.strip_suffix(']'))
+        .ok_or_else(|| EvalParamsError::InvalidValue(key.to_string(), value.to_string()))?;
+    let values = list
+        .split(',')
+        .filter(|v| !v.trim().is_empty())
+        .map(|v| parse_value(key, v))
+        .collect::<EvalParamsResult<Vec<f32>>>()?;
+    if values.len() != expected {
+        return Err(EvalParamsError::InvalidLength(
+            key.to_string(),
+            values.len(),
+        ));
+    }
+    Ok(values)
+}
+
+impl FromStr for EvalParams {
+    type Err = EvalParamsError;
+
+    fn from_str(s: &str) -> EvalParamsResult<EvalParams> {
+        let mut params = EvalParams::default();
+        for line in s.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| EvalParamsError::InvalidLine(line.to_string()))?;
+            let key = key.trim();
+            match key {
+                "pit_weights" => {
+                    let values = parse_list(key, value, 16)?;
+                    params.pit_weights.copy_from_slice(&values);
+                }
+                "capture_seeds_bonus" => params.capture_seeds_bonus = parse_value(key, value)?,
+                "nyumba_capture_bonus" => params.nyumba_capture_bonus = parse_value(key, value)?,
+                _ => match FEATURES.iter().find(|f| f.name() == key) {
+                    Some(feature) => {
+                        params.feature_weights[*feature as usize] = parse_value(key, value)?
+                    }
+                    None => return Err(EvalParamsError::UnknownKey(key.to_string())),
+                },
+            }
+        }
+        Ok(params)
+    }
+}
+
+impl Display for EvalParams {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        writeln!(f, "# Kikande evaluation parameters")?;
+        writeln!(f)?;
+        writeln!(f, "# Weight of a seed in each pit.")?;
+        let pit_weights: Vec<String> = self.pit_weights.iter().map(|w| w.to_string()).collect();
+        writeln!(f, "pit_weights = [{}]", pit_weights.join(", "))?;
+        writeln!(f)?;
+        writeln!(f, "# Feature weights.")?;
+        for feature in FEATURES {
+            writeln!(f, "{} = {}", feature.name(), self.feature_weight(feature))?;
+        }
+        writeln!(f)?;
+        writeln!(f, "# Move ordering bonuses.")?;
+        writeln!(f, "capture_seeds_bonus = {}", self.capture_seeds_bonus)?;
+        writeln!(f, "nyumba_capture_bonus = {}", self.nyumba_capture_bonus)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_eval_params_roundtrip() -> EvalParamsResult<()> {
+        let mut params = EvalParams::default();
+        params.pit_weights[4] = 1.5;
+        params.feature_weights[Feature::Exposure as usize] = -0.25;
+        params.nyumba_capture_bonus = 3.0;
+        let parsed: EvalParams = params.to_string().parse()?;
+        assert_eq!(parsed, params);
+        Ok(())
+    }
+
+    #[test]
+    fn test_eval_params_partial() -> EvalParamsResult<()> {
+        let params: EvalParams = "# Only mobility\nmobility = 0.3\n".parse()?;
+        assert_eq!(params.feature_weight(Feature::Mobility), 0.3);
+        assert_eq!(params.feature_weight(Feature::Material), 1.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_eval_params_errors() {
+        match "unknown = 1.0".parse::<EvalParams>() {
+            Err(e) => assert_eq!(e.to_string(), "UnknownKey: unknown"),
+            _ => panic!(),
+        }
+        match "mobility = x".parse::<EvalParams>() {
+            Err(e) => assert_eq!(e.to_string(), "InvalidValue: mobility = x"),
+            _ => panic!(),
+        }
+        match "pit_weights = [1.0, 2.0]".parse::<EvalParams>() {
+            Err(e) => assert_eq!(e.to_string(), "InvalidLength: pit_weights has 2 values"),
+            _ => panic!(),
+        }
+        match "mobility".parse::<EvalParams>() {
+            Err(e) => assert_eq!(e.to_string(), "InvalidLine: mobility"),
+            _ => panic!(),
+        }
+    }
+}
@@ -1,22 +1,178 @@
+use crate::bao::game::Game;
+use crate::bao::move_factory::MoveFactory;
+use crate::bao::moves::Move;
+use crate::bao::player::Player;
+use crate::search::eval_params::EvalParams;
 use crate::search::move_picker::ScoredMove;
 use crate::search::node::Node;
 
-static WEIGHTS: &[f32] = &[
-    1.0, // Kichwa
-    1.0, 1.0, 1.0, // Opponent's nyumba
-    1.0, // Player's nyumba
-    1.0, 1.0, 1.0, // Kichwa
-    1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0,
+/// Evaluation features. Every feature is computed as the difference between
+/// the player to move and the opponent.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Feature {
+    /// Seeds on the board, weighted per pit.
+    Material,
+    /// Number of legal capture moves.
+    CaptureMobility,
+    /// Number of legal moves.
+    Mobility,
+    /// Whether the nyumba is owned and still holds enough seeds to be used.
+    Nyumba,
+    /// Seeds in the front row minus seeds in the back row.
+    FrontRow,
+    /// Seeds that the other player can capture on their next turn.
+    Exposure,
+    /// Seeds that are still in hand during the namua stage.
+    SeedsInHand,
+}
+
+pub const NUM_FEATURES: usize = 7;
+
+/// All evaluation features in weight order.
+pub const FEATURES: [Feature; NUM_FEATURES] = [
+    Feature::Material,
+    Feature::CaptureMobility,
+    Feature::Mobility,
+    Feature::Nyumba,
+    Feature::FrontRow,
+    Feature::Exposure,
+    Feature::SeedsInHand,
 ];
 
-pub fn evaluate(node: Node, _legal_moves: &[ScoredMove]) -> f32 {
-    let mut score = 0.0;
+impl Feature {
+    /// Returns the name of the feature in parameter files.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Feature::Material => "material",
+            Feature::CaptureMobility => "capture_mobility",
+            Feature::Mobility => "mobility",
+            Feature::Nyumba => "nyumba",
+            Feature::FrontRow => "front_row",
+            Feature::Exposure => "exposure",
+            Feature::SeedsInHand => "seeds_in_hand",
+        }
+    }
+}
+
+/// Minimum number of seeds for the nyumba to be functional.
+const NYUMBA_MIN_SEEDS: u8 = 6;
+
+pub fn evaluate(node: Node, legal_moves: &[ScoredMove], params: &EvalParams) -> f32 {
+    features(&node, legal_moves, params)
+        .iter()
+        .zip(params.feature_weights.iter())
+        .map(|(feature, weight)| feature * weight)
+        .sum()
+}
 
-    // Material evaluation
-    for (i, weight) in WEIGHTS.iter().enumerate().take(16) {
-        score += weight * node.game.current_player.mashumo.get_seeds(i as i8) as f32;
-        score -= weight * node.game.other_player.mashumo.get_seeds(i as i8) as f32;
+/// Computes the unweighted evaluation features of a node.
+///
+/// # Arguments
+///
+/// * `node` - The node to evaluate.
+/// * `legal_moves` - The legal moves of the player to move.
+/// * `params` - The evaluation parameters, only the pit weights are used.
+pub fn features(
+    node: &Node,
+    legal_moves: &[ScoredMove],
+    params: &EvalParams,
+) -> [f32; NUM_FEATURES] {
+    let game = &node.game;
+    let current = &game.current_player;
+    let other = &game.other_player;
+
+    // The opponent's moves as if it were their turn.
+    let mut swapped = game.clone();
+    std::mem::swap(&mut swapped.current_player, &mut swapped.other_player);
+    let mut factory = MoveFactory::new(&swapped);
+    let other_moves = factory.get_legal_moves().to_vec();
+    let current_moves: Vec<Move> = legal_moves.iter().map(|m| m.m).collect();
+
+    let mut features = [0.0; NUM_FEATURES];
+    features[Feature::Material as usize] = material(current, params) - material(other, params);
+    features[Feature::CaptureMobility as usize] =
+        count_captures(&current_moves) - count_captures(&other_moves);
+    features[Feature::Mobility as usize] = current_moves.len() as f32 - other_moves.len() as f32;
+    features[Feature::Nyumba as usize] = nyumba(current) - nyumba(other);
+    features[Feature::FrontRow as usize] = front_row(current) - front_row(other);
+    features[Feature::Exposure as usize] =
+        exposure(&swapped, &other_moves) - exposure(game, &current_moves);
+    features[Feature::SeedsInHand as usize] = current.seeds as f32 - other.seeds as f32;
+    features
+}
+
+fn material(player: &Player, params: &EvalParams) -> f32 {
+    params
+        .pit_weights
+        .iter()
+        .enumerate()
+        .map(|(i, weight)| weight * player.mashumo.get_seeds(i as i8) as f32)
+        .sum()
+}
+
+fn count_captures(moves: &[Move]) -> f32 {
+    moves.iter().filter(|m| m.is_capture()).count() as f32
+}
+
+fn nyumba(player: &Player) -> f32 {
+    match player.nyumba && player.mashumo.get_seeds(4) >= NYUMBA_MIN_SEEDS {
+        true => 1.0,
+        false => 0.0,
+    }
+}
+
+fn front_row(player: &Player) -> f32 {
+    let front: u32 = player.mashumo.mashumo[..8].iter().map(|&s| s as u32).sum();
+    let back: u32 = player.mashumo.mashumo[8..].iter().map(|&s| s as u32).sum();
+    front as f32 - back as f32
+}
+
+/// Returns the number of seeds of the other player that the player to move
+/// can capture with one of the given moves.
+fn exposure(game: &Game, moves: &[Move]) -> f32 {
+    let factory = MoveFactory::new(game);
+    let mut captured = 0u8;
+    for m in moves {
+        if let Some(capture_index) = factory.get_capture_index(m) {
+            captured |= 1 << capture_index;
+        }
+    }
+    (0..8)
+        .filter(|i| captured & (1 << i) != 0)
+        .map(|i| game.other_player.mashumo.get_seeds(i) as f32)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::bao::game_builder::GameBuilder;
+    use crate::search::move_picker::MovePicker;
+
+    fn evaluate_game(game: Game) -> [f32; NUM_FEATURES] {
+        let node = Node::new(game);
+        let mut picker = MovePicker::new();
+        let params = EvalParams::default();
+        let legal_moves = picker.pick_moves(&node.game, None, &params).to_vec();
+        features(&node, &legal_moves, &params)
     }
 
-    score
+    #[test]
+    fn test_features_starting_position() {
+        // The starting position is symmetric.
+        let features = evaluate_game(Game::new());
+        assert_eq!(features, [0.0; NUM_FEATURES]);
+    }
+
+    #[test]
+    fn test_features_after_capture() {
+        let game = bao_game!("6L", "5R");
+        let features = evaluate_game(game);
+        // Both players have 21 seeds in hand, but player 2 captured two seeds.
+        assert_eq!(features[Feature::SeedsInHand as usize], 0.0);
+        assert_eq!(features[Feature::Material as usize], -2.0);
+        assert_eq!(features[Feature::FrontRow as usize], -2.0);
+        assert_eq!(features[Feature::Exposure as usize], -1.0);
+    }
 }
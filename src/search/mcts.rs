@@ -0,0 +1,421 @@
+//! Monte Carlo tree search with UCT selection.
+
+use crate::bao::game::Game;
+use crate::bao::move_factory::MoveFactory;
+use crate::bao::moves::Move;
+use crate::bao::pv::PVLine;
+use crate::config::SearchConfig;
+use crate::display;
+use crate::error::{SearchError, SearchResult};
+use crate::search::book::position_hash;
+use crate::search::eval_params::EvalParams;
+use crate::search::move_picker::MovePicker;
+use crate::search::searcher::{self, Searcher};
+use crate::search::timer::Timer;
+use rand::rngs::ThreadRng;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::thread;
+
+/// Playouts that are not decided after this many plies count as draws.
+const MAX_PLAYOUT_PLIES: usize = 200;
+
+/// The probability of a heuristic playout to play a random move instead of
+/// the best ordered one.
+const HEURISTIC_EPSILON: f64 = 0.25;
+
+/// How moves are chosen during playouts.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Playout {
+    /// Uniformly random legal moves.
+    #[default]
+    Random,
+    /// Mostly the best move according to the move ordering.
+    Heuristic,
+}
+
+impl FromStr for Playout {
+    type Err = SearchError;
+
+    fn from_str(s: &str) -> SearchResult<Playout> {
+        match s {
+            "random" => Ok(Playout::Random),
+            "heuristic" => Ok(Playout::Heuristic),
+            _ => Err(SearchError::InvalidPlayout(s.to_string())),
+        }
+    }
+}
+
+impl Display for Playout {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Playout::Random => write!(f, "random"),
+            Playout::Heuristic => write!(f, "heuristic"),
+        }
+    }
+}
+
+/// Configuration of the Monte Carlo tree search.
+#[derive(Clone, Debug)]
+pub struct MctsConfig {
+    /// The number of playouts per search, shared by all threads.
+    pub iterations: u32,
+    /// The exploration constant of the UCT formula.
+    pub exploration: f64,
+    pub playout: Playout,
+}
+
+impl Default for MctsConfig {
+    fn default() -> Self {
+        MctsConfig {
+            iterations: 10_000,
+            exploration: std::f64::consts::SQRT_2,
+            playout: Playout::Random,
+        }
+    }
+}
+
+struct MctsNode {
+    game: Game,
+    /// The move leading to the node.
+    m: Option<Move>,
+    children: Vec<usize>,
+    /// Legal moves that have no child yet.
+    untried: Vec<Move>,
+    visits: u32,
+    /// The sum of the rewards of the player who made the move to the node.
+    reward: f64,
+}
+
+impl MctsNode {
+    fn new(game: Game, m: Option<Move>) -> MctsNode {
+        let untried = match game.is_over() {
+            true => vec![],
+            false => MoveFactory::new(&game).get_legal_moves().to_vec(),
+        };
+        MctsNode {
+            game,
+            m,
+            children: vec![],
+            untried,
+            visits: 0,
+            reward: 0.0,
+        }
+    }
+
+    fn mean_reward(&self) -> f64 {
+        self.reward / self.visits.max(1) as f64
+    }
+}
+
+/// A search tree stored in an arena with the root at index zero.
+struct Tree {
+    nodes: Vec<MctsNode>,
+}
+
+impl Tree {
+    fn new(game: Game) -> Tree {
+        Tree {
+            nodes: vec![MctsNode::new(game, None)],
+        }
+    }
+
+    /// Returns the subtree of a position at most two plies below the root, so
+    /// that the statistics of the last search can be reused after a move and
+    /// its reply.
+    fn reroot(self, game: &Game) -> Option<Tree> {
+        let hash = position_hash(game);
+        let mut candidates = vec![0];
+        for _ in 0..3 {
+            let found = candidates
+                .iter()
+                .find(|&&i| position_hash(&self.nodes[i].game) == hash);
+            if let Some(&index) = found {
+                return Some(self.subtree(index));
+            }
+            candidates = candidates
+                .iter()
+                .flat_map(|&i| self.nodes[i].children.iter().copied())
+                .collect();
+        }
+        None
+    }
+
+    /// Moves the nodes below `index` into a new tree. The nodes keep their
+    /// children and untried moves, so no moves are generated again.
+    fn subtree(self, index: usize) -> Tree {
+        let mut old_nodes: Vec<Option<MctsNode>> = self.nodes.into_iter().map(Some).collect();
+        let mut nodes = vec![];
+        let mut stack = vec![(index, None)];
+        while let Some((old, parent)) = stack.pop() {
+            let mut node = old_nodes[old].take().expect("Node has two parents");
+            let children = std::mem::take(&mut node.children);
+            let new = nodes.len();
+            nodes.push(node);
+            if let Some(parent) = parent {
+                let parent: &mut MctsNode = &mut nodes[parent];
+                parent.children.push(new);
+            }
+            stack.extend(children.into_iter().map(|child| (child, Some(new))));
+        }
+        nodes[0].m = None;
+        Tree { nodes }
+    }
+
+    /// Runs one selection, expansion, playout and backpropagation step.
+    fn iterate(&mut self, config: &MctsConfig, params: &EvalParams, rng: &mut ThreadRng) {
+        // Selection.
+        let mut path = vec![0];
+        let mut index = 0;
+        while self.nodes[index].untried.is_empty() && !self.nodes[index].children.is_empty() {
+            index = self.select_child(index, config.exploration);
+            path.push(index);
+        }
+
+        // Expansion.
+        if !self.nodes[index].untried.is_empty() {
+            let node = &mut self.nodes[index];
+            let i = rng.gen_range(0..node.untried.len());
+            let m = node.untried.swap_remove(i);
+            let mut game = node.game.clone();
+            game.take_turn(&m);
+            self.nodes.push(MctsNode::new(game, Some(m)));
+            let child = self.nodes.len() - 1;
+            self.nodes[index].children.push(child);
+            index = child;
+            path.push(index);
+        }
+
+        // Playout, rewarding the player to move at the leaf.
+        let mut reward = playout(&self.nodes[index].game, config.playout, params, rng);
+
+        // Backpropagation. Every node stores the reward of the player who moved into it.
+        for &i in path.iter().rev() {
+            reward = 1.0 - reward;
+            self.nodes[i].visits += 1;
+            self.nodes[i].reward += reward;
+        }
+    }
+
+    fn select_child(&self, index: usize, exploration: f64) -> usize {
+        let log_visits = (self.nodes[index].visits.max(1) as f64).ln();
+        let uct = |child: usize| {
+            let node = &self.nodes[child];
+            let visits = node.visits.max(1) as f64;
+            node.mean_reward() + exploration * (log_visits / visits).sqrt()
+        };
+        *self.nodes[index]
+            .children
+            .iter()
+            .max_by(|&&a, &&b| uct(a).total_cmp(&uct(b)))
+            .expect("No children")
+    }
+
+    /// Returns the child with the most visits.
+    fn best_child(&self, index: usize) -> Option<usize> {
+        self.nodes[index]
+            .children
+            .iter()
+            .copied()
+            .max_by_key(|&child| self.nodes[child].visits)
+    }
+
+    /// Returns the visits and rewards of the root moves.
+    fn root_stats(&self) -> Vec<(Move, u32, f64)> {
+        self.nodes[0]
+            .children
+            .iter()
+            .map(|&child| {
+                let node = &self.nodes[child];
+                (
+                    node.m.expect("Child without move"),
+                    node.visits,
+                    node.reward,
+                )
+            })
+            .collect()
+    }
+
+    /// Returns the line of most visited moves, starting with the given move.
+    fn pv_line(&self, first: Move) -> Vec<Move> {
+        let mut moves = vec![first];
+        let mut index = self.nodes[0]
+            .children
+            .iter()
+            .copied()
+            .find(|&child| self.nodes[child].m == Some(first));
+        while let Some(child) = index.and_then(|i| self.best_child(i)) {
+            moves.push(self.nodes[child].m.expect("Child without move"));
+            index = Some(child);
+        }
+        moves
+    }
+}
+
+/// Plays a game to the end and returns the reward of the player to move:
+/// one for a win, zero for a loss and a half for an undecided game.
+fn playout(game: &Game, playout: Playout, params: &EvalParams, rng: &mut ThreadRng) -> f64 {
+    let mut game = game.clone();
+    let mut picker = MovePicker::new();
+    for ply in 0..MAX_PLAYOUT_PLIES {
+        let moves = match playout {
+            Playout::Heuristic if !rng.gen_bool(HEURISTIC_EPSILON) => {
+                let moves = picker.pick_moves(&game, None, params);
+                &moves[..moves.len().min(1)]
+            }
+            _ => picker.pick_moves_unordered(&game),
+        };
+        let m = match moves.choose(rng) {
+            Some(m) if game.current_player.mashumo.bitboard != 0 => m.m,
+            // The player to move has lost.
+            _ => return if ply % 2 == 0 { 0.0 } else { 1.0 },
+        };
+        game.take_turn(&m);
+    }
+    0.5
+}
+
+/// Monte Carlo tree search with root parallelism. Every thread grows its own
+/// tree and the visits of the root moves are summed up. The trees are kept
+/// and reused when the next search starts from one of their positions.
+pub struct MctsSearcher {
+    config: SearchConfig,
+    trees: Vec<Tree>,
+}
+
+impl MctsSearcher {
+    pub fn new(config: SearchConfig) -> Self {
+        MctsSearcher {
+            config,
+            trees: vec![],
+        }
+    }
+}
+
+impl Searcher for MctsSearcher {
+    fn search(&mut self, game: &Game, verbose: bool) -> SearchResult<PVLine> {
+        if let Some(pvline) = searcher::probe(&self.config, game, verbose) {
+            return Ok(pvline);
+        }
+
+        // Reuse the trees of the last search if the position is in them.
+        let num_threads = self.config.num_threads.max(1) as usize;
+        let mut trees: Vec<Tree> = std::mem::take(&mut self.trees)
+            .into_iter()
+            .filter_map(|tree| tree.reroot(game))
+            .collect();
+        trees.resize_with(num_threads, || Tree::new(game.clone()));
+
+        // Grow the trees in parallel.
+        let timer = Timer::new(self.config.max_time_ms.unwrap_or(u32::MAX))
+            .with_max_nodes(self.config.max_nodes);
+        let mcts = &self.config.mcts;
+        let params = &self.config.eval_params;
+        let iterations = mcts.iterations.div_ceil(num_threads as u32);
+        thread::scope(|scope| {
+            for tree in trees.iter_mut() {
+                let timer = timer.clone();
+                scope.spawn(move || {
+                    let mut rng = rand::thread_rng();
+                    for _ in 0..iterations {
+                        if timer.is_time_up() {
+                            break;
+                        }
+                        tree.iterate(mcts, params, &mut rng);
+                    }
+                });
+            }
+        });
+
+        // Sum up the root statistics of all trees.
+        let mut stats: Vec<(Move, u32, f64)> = vec![];
+        for (m, visits, reward) in trees.iter().flat_map(Tree::root_stats) {
+            match stats.iter_mut().find(|(n, _, _)| *n == m) {
+                Some(entry) => {
+                    entry.1 += visits;
+                    entry.2 += reward;
+                }
+                None => stats.push((m, visits, reward)),
+            }
+        }
+        let pvline = match stats.iter().max_by_key(|(_, visits, _)| *visits) {
+            Some(&(m, visits, reward)) => PVLine {
+                moves: trees[0].pv_line(m),
+                // Map the win rate to [-1, 1].
+                value: (2.0 * reward / visits.max(1) as f64 - 1.0) as f32,
+            },
+            None => PVLine::default(),
+        };
+
+        if verbose {
+            display::clear_terminal();
+            display::print_game(game);
+            println!("{}", timer);
+            let playouts: u32 = trees.iter().map(|tree| tree.nodes[0].visits).sum();
+            println!("Playouts: {}", playouts);
+            display::print_pvlines(std::slice::from_ref(&pvline));
+        }
+
+        self.trees = trees;
+        Ok(pvline)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::bao::notation;
+    use crate::config::SearcherKind;
+
+    fn mcts_config(iterations: u32, num_threads: u8) -> SearchConfig {
+        let mut config = SearchConfig::new(1, num_threads, None);
+        config.searcher = SearcherKind::Mcts;
+        config.mcts.iterations = iterations;
+        config
+    }
+
+    #[test]
+    fn test_mcts_finds_winning_capture() -> SearchResult<()> {
+        let game = notation::from_notation(
+            "0,1,0,0,0,0,0,0,0,0,0,0,0,0,3,0/0,0,0,0,0,0,2,0,2,0,0,0,0,0,0,0 0/0 00",
+        )
+        .unwrap();
+        let mut searcher = MctsSearcher::new(mcts_config(200, 2));
+        let pvline = searcher.search(&game, false)?;
+        assert_eq!(pvline.moves[0], Move::mtaji_capture_right(14));
+        assert_eq!(pvline.value, 1.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_mcts_reuses_tree() -> SearchResult<()> {
+        let mut searcher = MctsSearcher::new(mcts_config(500, 1));
+        let game = Game::new();
+        let pvline = searcher.search(&game, false)?;
+        assert!(!pvline.moves.is_empty());
+
+        // After the best move and its most visited reply the tree is kept.
+        let mut next = game.clone();
+        next.take_turn(&pvline.moves[0]);
+        next.take_turn(&pvline.moves[1]);
+        let tree = std::mem::take(&mut searcher.trees).remove(0);
+        let tree = tree.reroot(&next).expect("Position not in tree");
+        let root = &tree.nodes[0];
+        assert!(root.visits > 0);
+        assert!(root.m.is_none());
+        // The root keeps its children and untried moves.
+        let num_moves = MoveFactory::new(&next).get_legal_moves().len();
+        assert_eq!(root.children.len() + root.untried.len(), num_moves);
+        assert!(root.children.iter().all(|&i| tree.nodes[i].m.is_some()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_playout_str() {
+        assert_eq!("heuristic".parse::<Playout>().unwrap(), Playout::Heuristic);
+        assert!("smart".parse::<Playout>().is_err());
+    }
+}
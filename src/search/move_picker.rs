@@ -1,22 +1,23 @@
 use crate::bao::game::Game;
 use crate::bao::move_factory::MoveFactory;
 use crate::bao::moves::Move;
+use crate::search::eval_params::EvalParams;
 
 #[derive(Copy, Clone)]
 pub struct ScoredMove {
     pub m: Move,
-    score: i8,
+    score: f32,
 }
 
 impl ScoredMove {
-    pub fn new(m: Move, score: i8) -> ScoredMove {
+    pub fn new(m: Move, score: f32) -> ScoredMove {
         ScoredMove { m, score }
     }
 }
 
 impl Default for ScoredMove {
     fn default() -> ScoredMove {
-        ScoredMove::new(Move { index: 0, flags: 0 }, 0)
+        ScoredMove::new(Move { index: 0, flags: 0 }, 0.0)
     }
 }
 
@@ -34,18 +35,17 @@ impl MovePicker {
         }
     }
 
-    pub fn pick_moves(&mut self, game: &Game, tt_move: Option<Move>) -> &[ScoredMove] {
-        // Generate legal moves.
-        let mut move_factory = MoveFactory::new(game);
-        let legal_moves = move_factory.get_legal_moves();
-        self.num_moves = legal_moves.len();
-        for (i, legal_move) in legal_moves.iter().enumerate().take(self.num_moves) {
-            self.moves[i].m = *legal_move;
-        }
+    pub fn pick_moves(
+        &mut self,
+        game: &Game,
+        tt_move: Option<Move>,
+        params: &EvalParams,
+    ) -> &[ScoredMove] {
+        self.generate(game);
 
         // Sort by value.
-        self.score(game);
-        self.moves[..self.num_moves].sort_by(|a, b| b.score.cmp(&a.score));
+        self.score(game, params);
+        self.moves[..self.num_moves].sort_by(|a, b| b.score.total_cmp(&a.score));
 
         // Search tt move first.
         self.insert_tt_move(tt_move);
@@ -53,6 +53,21 @@ impl MovePicker {
         &self.moves[..self.num_moves]
     }
 
+    /// Generates the legal moves without ordering them.
+    pub fn pick_moves_unordered(&mut self, game: &Game) -> &[ScoredMove] {
+        self.generate(game);
+        &self.moves[..self.num_moves]
+    }
+
+    fn generate(&mut self, game: &Game) {
+        let mut move_factory = MoveFactory::new(game);
+        let legal_moves = move_factory.get_legal_moves();
+        self.num_moves = legal_moves.len();
+        for (i, legal_move) in legal_moves.iter().enumerate().take(self.num_moves) {
+            self.moves[i].m = *legal_move;
+        }
+    }
+
     fn insert_tt_move(&mut self, tt_move: Option<Move>) {
         if let Some(tt_move) = tt_move {
             for i in 0..self.num_moves {
@@ -64,17 +79,18 @@ impl MovePicker {
         }
     }
 
-    fn score(&mut self, game: &Game) {
+    fn score(&mut self, game: &Game, params: &EvalParams) {
         for m in &mut self.moves[..self.num_moves] {
             if m.m.is_capture() {
                 let capture_index = 7 - m.m.index;
                 // Bonus for protecting own seeds
-                m.score += game.current_player.mashumo.get_seeds(m.m.index) as i8;
+                m.score += params.capture_seeds_bonus
+                    * game.current_player.mashumo.get_seeds(m.m.index) as f32;
                 // Bonus for protecting nyumba
                 m.score += if capture_index == 3 && game.current_player.nyumba {
-                    2
+                    params.nyumba_capture_bonus
                 } else {
-                    0
+                    0.0
                 };
             }
         }
@@ -8,15 +8,30 @@ use crate::error::SearchResult;
 use crate::search::evaluate;
 use crate::search::move_picker::MovePicker;
 use crate::search::node::Node;
+use crate::search::quiescence;
+use crate::search::searcher;
 use crate::search::timer::Timer;
+use crate::search::trace::{TraceNode, Tracer, TtHit};
 use crate::search::transposition_table::{EntryType, TranspositionTable};
 use std::sync::Arc;
+use std::time::Duration;
 
 use crate::display;
 use std::sync::mpsc;
 
 use std::thread;
 
+/// Progress of a running search, reported whenever a thread finishes a depth.
+pub struct SearchInfo<'a> {
+    /// The deepest finished depth.
+    pub depth: usize,
+    /// The principal variation lines found so far, best first.
+    pub pvlines: &'a [PVLine],
+    pub elapsed: Duration,
+    /// The number of nodes searched by all threads.
+    pub nodes: u64,
+}
+
 /// Search for the best move.
 ///
 /// # Arguments
@@ -29,12 +44,45 @@ use std::thread;
 ///
 /// A principal variation line.
 pub fn search(config: SearchConfig, game: Game, verbose: bool) -> SearchResult<PVLine> {
+    // Play from the opening book or the tablebases.
+    if let Some(pvline) = searcher::probe(&config, &game, verbose) {
+        return Ok(pvline);
+    }
+
+    search_with_observer(config, game.clone(), |info| {
+        if verbose {
+            // Display the top 3 PV lines.
+            display::clear_terminal();
+            display::print_game(&game);
+            println!("Δt: {:?}", info.elapsed);
+            display::print_pvlines(info.pvlines);
+        }
+    })
+}
+
+/// Search for the best move and report the progress to an observer. Unlike
+/// `search`, it does not play from the opening book or the tablebases.
+///
+/// # Arguments
+///
+/// * `config` - The search configuration.
+/// * `game` - The position to search from.
+/// * `observer` - Called with the progress whenever a thread finishes a depth.
+///
+/// # Returns
+///
+/// A principal variation line.
+pub fn search_with_observer(
+    config: SearchConfig,
+    game: Game,
+    mut observer: impl FnMut(&SearchInfo),
+) -> SearchResult<PVLine> {
     // Initialize shared transposition table and channels.
     let tt = Arc::new(TranspositionTable::new());
     let (sender, receiver) = mpsc::channel();
 
     // Initialize timer.
-    let timer = Timer::new(config.max_time_ms.unwrap_or(u32::MAX));
+    let timer = Timer::new(config.max_time_ms.unwrap_or(u32::MAX)).with_max_nodes(config.max_nodes);
 
     // Start multi-threaded iterative deepening.
     for _ in 0..config.num_threads {
@@ -42,14 +90,15 @@ pub fn search(config: SearchConfig, game: Game, verbose: bool) -> SearchResult<P
         let sender = sender.clone();
         let timer = timer.clone();
         let tt = tt.clone();
+        let config = config.clone();
         thread::spawn(move || -> SearchResult<()> {
-            iterative_deepening(config.max_depth, node, sender, &timer, &tt)
+            iterative_deepening(node, sender, &timer, &tt, &config)
         });
     }
 
     drop(sender); // Drop so threads can finish.
 
-    // Main thread displays the board and PV lines.
+    // Main thread collects the PV lines.
     let mut pvlines: Vec<PVLine> = vec![];
     let mut current_search_depth = 0;
     while let Ok(pvline) = receiver.recv() {
@@ -60,48 +109,115 @@ pub fn search(config: SearchConfig, game: Game, verbose: bool) -> SearchResult<P
         pvlines.push(pvline);
         pvlines.sort_by(|a, b| a.partial_cmp(b).expect("Failed to compare PVLine"));
 
-        if verbose {
-            // Display the top 3 PV lines.
-            display::clear_terminal();
-            display::print_game(&game);
-            println!("{}", timer);
-            display::print_pvlines(&pvlines);
-        }
+        observer(&SearchInfo {
+            depth: current_search_depth,
+            pvlines: &pvlines,
+            elapsed: timer.elapsed(),
+            nodes: timer.nodes(),
+        });
+    }
+
+    // Return the best PV line. It's empty if not even the first depth finished.
+    Ok(pvlines.into_iter().next().unwrap_or_default())
+}
+
+/// The state shared by the nodes of a search.
+struct SearchContext<'a> {
+    timer: &'a Timer,
+    tt: &'a TranspositionTable,
+    config: &'a SearchConfig,
+}
+
+/// Search a position single-threaded and record the searched tree. The
+/// shallower depths are searched first without tracing, so the transposition
+/// table is filled like in a regular search.
+///
+/// # Arguments
+///
+/// * `config` - The search configuration.
+/// * `game` - The root position.
+/// * `max_trace_depth` - The number of plies below the root to record.
+///
+/// # Returns
+///
+/// The searched tree of the last depth, or none if the search ran out of time.
+pub fn trace(config: &SearchConfig, game: &Game, max_trace_depth: usize) -> Option<TraceNode> {
+    let tt = TranspositionTable::new();
+    let timer = Timer::new(config.max_time_ms.unwrap_or(u32::MAX)).with_max_nodes(config.max_nodes);
+    let context = SearchContext {
+        timer: &timer,
+        tt: &tt,
+        config,
+    };
+    let node = Node::new(game.clone());
+    for depth in 1..config.max_depth {
+        negamax(
+            node.clone(),
+            depth,
+            f32::NEG_INFINITY,
+            f32::INFINITY,
+            &context,
+            &mut Tracer::disabled(),
+        );
     }
 
-    // Return the best PV line.
-    Ok(pvlines.remove(0))
+    let mut tracer = Tracer::new(max_trace_depth);
+    tracer.enter(
+        game,
+        None,
+        config.max_depth,
+        f32::NEG_INFINITY,
+        f32::INFINITY,
+    );
+    let value = negamax(
+        node,
+        config.max_depth,
+        f32::NEG_INFINITY,
+        f32::INFINITY,
+        &context,
+        &mut tracer,
+    );
+    tracer.exit(value);
+    match timer.is_time_up() {
+        true => None,
+        false => tracer.into_trace(),
+    }
 }
 
 /// Iterative deepening negamax search.
 ///
 /// # Arguments
 ///
-/// * `max_depth` - The maximum depth to search.
 /// * `node` - The root node to search from.
 /// * `sender` - The channel to send the principal variation line.
 /// * `timer` - The timer to check if time is up.
 /// * `tt` - The transposition table to store results.
+/// * `config` - The search configuration.
 fn iterative_deepening(
-    max_depth: u8,
     node: Node,
     sender: std::sync::mpsc::Sender<PVLine>,
     timer: &Timer,
     tt: &Arc<TranspositionTable>,
+    config: &SearchConfig,
 ) -> SearchResult<()> {
-    for depth in 1..max_depth + 1 {
-        negamax(
+    let context = SearchContext { timer, tt, config };
+    for depth in 1..config.max_depth + 1 {
+        let value = negamax(
             node.clone(),
             depth,
             f32::NEG_INFINITY,
             f32::INFINITY,
-            timer,
-            tt,
-        )?;
+            &context,
+            &mut Tracer::disabled(),
+        );
         if timer.is_time_up() {
             break; // We can't guarantee the tree is fully searched.
         }
-        let pvline = tt.get_pv_line(&node, depth);
+        // The line is empty if every move loses.
+        let mut pvline = tt.get_pv_line(&node, depth);
+        pvline.value = value;
+        // Count all nodes of the depth before reporting it.
+        timer.flush();
         sender.send(pvline)?;
     }
     Ok(())
@@ -115,19 +231,28 @@ fn iterative_deepening(
 /// * `depth` - The maximum depth to search.
 /// * `alpha` - The alpha value.
 /// * `beta` - The beta value.
-/// * `timer` - The timer to check if time is up.
-/// * `tt` - The transposition table to store results.
+/// * `context` - The timer, transposition table and search configuration.
+/// * `tracer` - Records the searched tree.
 fn negamax(
     node: Node,
     depth: u8,
     mut alpha: f32,
     mut beta: f32,
-    timer: &Timer,
-    tt: &Arc<TranspositionTable>,
-) -> SearchResult<f32> {
+    context: &SearchContext,
+    tracer: &mut Tracer,
+) -> f32 {
+    let SearchContext { timer, tt, config } = *context;
+
     // Time's up, return immediately. Returning 0.0 does not affect the result.
     if timer.is_time_up() {
-        return Ok(0.0);
+        return 0.0;
+    }
+
+    // Covered endgames are solved.
+    if let Some(tablebases) = &config.tablebases {
+        if let Some(outcome) = tablebases.probe(&node.game) {
+            return outcome.score();
+        }
     }
 
     let orig_alpha = alpha;
@@ -139,9 +264,7 @@ fn negamax(
         // Use tt entry if it's valid.
         if entry.depth >= depth {
             match entry.entry_type {
-                EntryType::Exact => {
-                    return Ok(entry.score);
-                }
+                EntryType::Exact => {}
                 EntryType::LowerBound => {
                     alpha = alpha.max(entry.score);
                 }
@@ -149,25 +272,36 @@ fn negamax(
                     beta = beta.min(entry.score);
                 }
             }
-            if alpha >= beta {
-                return Ok(entry.score);
+            let is_cutoff = entry.entry_type == EntryType::Exact || alpha >= beta;
+            tracer.tt_hit(entry.entry_type.into(), is_cutoff);
+            if is_cutoff {
+                return entry.score;
             }
+        } else {
+            tracer.tt_hit(TtHit::Move, false);
         }
         // Search tt move first.
         tt_move = Some(entry.best_move);
     }
 
     // Generate possible moves.
+    let params = &config.eval_params;
     let mut picker = MovePicker::new();
-    let legal_moves = picker.pick_moves(&node.game, tt_move);
+    let legal_moves = match config.move_ordering {
+        true => picker.pick_moves(&node.game, tt_move, params),
+        false => picker.pick_moves_unordered(&node.game),
+    };
 
     // Terminal node or max depth, evaluate and return.
     if legal_moves.is_empty() || node.game.current_player.mashumo.bitboard == 0 {
-        return Ok(f32::NEG_INFINITY);
+        return f32::NEG_INFINITY;
     } else if node.game.other_player.mashumo.bitboard == 0 {
-        return Ok(f32::INFINITY);
+        return f32::INFINITY;
+    } else if depth == 0 && config.quiescence {
+        let (score, _, _) = quiescence::quiesce(node, quiescence::MAX_DEPTH, alpha, beta, params);
+        return score;
     } else if depth == 0 {
-        return Ok(evaluate::evaluate(node, legal_moves));
+        return evaluate::evaluate(node, legal_moves, params);
     }
 
     // Recursive search
@@ -175,7 +309,10 @@ fn negamax(
     let mut best_move: Option<Move> = None;
     for m in legal_moves {
         let child = node.apply_move(&m.m);
-        value = value.max(-negamax(child, depth - 1, -beta, -alpha, timer, tt)?);
+        tracer.enter(&child.game, Some(m.m), depth - 1, -beta, -alpha);
+        let score = negamax(child, depth - 1, -beta, -alpha, context, tracer);
+        tracer.exit(score);
+        value = value.max(-score);
 
         if value > alpha {
             alpha = value;
@@ -183,6 +320,7 @@ fn negamax(
         }
 
         if alpha >= beta {
+            tracer.cutoff();
             break; // Beta cut-off
         }
     }
@@ -198,5 +336,51 @@ fn negamax(
         tt.insert(node.zobrist, &best_move, depth, value, tt_flag);
     }
 
-    Ok(value)
+    value
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::bao::move_factory::MoveFactory;
+
+    /// Searches the position with one transposition table entry for it and
+    /// returns the traced root.
+    fn trace_with_entry(game: &Game, depth: u8, score: f32, entry_type: EntryType) -> TraceNode {
+        let config = SearchConfig::default();
+        let tt = TranspositionTable::new();
+        let timer = Timer::new(u32::MAX);
+        let context = SearchContext {
+            timer: &timer,
+            tt: &tt,
+            config: &config,
+        };
+        let node = Node::new(game.clone());
+        let m = MoveFactory::new(game).get_legal_moves()[0];
+        tt.insert(node.zobrist, &m, depth + 1, score, entry_type);
+
+        let mut tracer = Tracer::new(1);
+        tracer.enter(game, None, depth, -1.0, 1.0);
+        let value = negamax(node, depth, -1.0, 1.0, &context, &mut tracer);
+        tracer.exit(value);
+        tracer.into_trace().expect("No root")
+    }
+
+    #[test]
+    fn test_trace_tt_hits() {
+        let game = Game::new();
+        let root = trace_with_entry(&game, 2, 0.5, EntryType::Exact);
+        assert_eq!((root.tt_hit, root.tt_cutoff), (TtHit::Exact, true));
+        assert_eq!(root.score, 0.5);
+        assert!(root.children.is_empty());
+
+        // A lower bound inside the window only narrows it.
+        let root = trace_with_entry(&game, 2, 0.5, EntryType::LowerBound);
+        assert_eq!((root.tt_hit, root.tt_cutoff), (TtHit::Lower, false));
+        assert!(!root.children.is_empty());
+
+        let root = trace_with_entry(&game, 2, 2.0, EntryType::LowerBound);
+        assert_eq!((root.tt_hit, root.tt_cutoff), (TtHit::Lower, true));
+    }
 }
@@ -0,0 +1,104 @@
+//! Quiescence search over capture sequences.
+
+use crate::search::eval_params::EvalParams;
+use crate::search::evaluate;
+use crate::search::move_picker::MovePicker;
+use crate::search::node::Node;
+
+/// The default maximum number of captures to resolve.
+pub const MAX_DEPTH: u8 = 4;
+
+/// Resolves captures until the position is quiet.
+///
+/// Captures are compulsory in Bao, so a position is quiet when the player to
+/// move has no capture. There is no stand-pat option in positions with
+/// captures; the search only evaluates them once `depth` is exhausted.
+///
+/// # Arguments
+///
+/// * `node` - The node to search from.
+/// * `depth` - The maximum number of captures to resolve.
+/// * `alpha` - The alpha value.
+/// * `beta` - The beta value.
+/// * `params` - The evaluation parameters.
+///
+/// # Returns
+///
+/// The score from the point of view of the player to move and the quiet leaf
+/// of the principal variation together with its distance in plies.
+pub fn quiesce(
+    node: Node,
+    depth: u8,
+    mut alpha: f32,
+    beta: f32,
+    params: &EvalParams,
+) -> (f32, Node, u8) {
+    let mut picker = MovePicker::new();
+    let legal_moves = picker.pick_moves(&node.game, None, params);
+
+    // Terminal node.
+    if legal_moves.is_empty() || node.game.current_player.mashumo.bitboard == 0 {
+        return (f32::NEG_INFINITY, node, 0);
+    } else if node.game.other_player.mashumo.bitboard == 0 {
+        return (f32::INFINITY, node, 0);
+    }
+
+    // Quiet node or max depth, evaluate and return.
+    if depth == 0 || !legal_moves[0].m.is_capture() {
+        let score = evaluate::evaluate(node.clone(), legal_moves, params);
+        return (score, node, 0);
+    }
+
+    let mut best: Option<(f32, Node, u8)> = None;
+    for m in legal_moves {
+        let child = node.apply_move(&m.m);
+        let (score, leaf, ply) = quiesce(child, depth - 1, -beta, -alpha, params);
+        let score = -score;
+        if best.as_ref().is_none_or(|(value, _, _)| score > *value) {
+            best = Some((score, leaf, ply + 1));
+        }
+        alpha = alpha.max(score);
+        if alpha >= beta {
+            break; // Beta cut-off
+        }
+    }
+    best.expect("No capture moves")
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::bao::game::Game;
+    use crate::bao::game_builder::GameBuilder;
+
+    #[test]
+    fn test_quiesce_quiet_position() {
+        // The starting position has no captures.
+        let params = EvalParams::default();
+        let (score, _, ply) = quiesce(
+            Node::new(Game::new()),
+            8,
+            f32::NEG_INFINITY,
+            f32::INFINITY,
+            &params,
+        );
+        assert_eq!(score, 0.0);
+        assert_eq!(ply, 0);
+    }
+
+    #[test]
+    fn test_quiesce_resolves_captures() {
+        // Player 2 has to capture after 6L.
+        let params = EvalParams::default();
+        let (score, _, ply) = quiesce(
+            Node::new(bao_game!("6L")),
+            2,
+            f32::NEG_INFINITY,
+            f32::INFINITY,
+            &params,
+        );
+        assert!(ply > 0 && ply <= 2);
+        assert!(score.is_finite());
+    }
+}
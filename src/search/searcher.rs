@@ -0,0 +1,104 @@
+//! Search algorithms behind a common interface.
+
+use crate::bao::game::Game;
+use crate::bao::pv::PVLine;
+use crate::config::{SearchConfig, SearcherKind};
+use crate::display;
+use crate::error::SearchResult;
+use crate::search::mcts::MctsSearcher;
+use crate::search::negamax;
+use crate::search::strength::StrengthSearcher;
+use crate::search::tablebase::Tablebases;
+
+/// A search algorithm that finds the best move of a position. Searchers may
+/// keep state between searches of the same game, such as a search tree.
+pub trait Searcher {
+    /// Searches for the best move.
+    ///
+    /// # Arguments
+    ///
+    /// * `game` - The position to search from.
+    /// * `verbose` - Whether to display the search progress in the terminal.
+    ///
+    /// # Returns
+    ///
+    /// A principal variation line, empty if no move was found.
+    fn search(&mut self, game: &Game, verbose: bool) -> SearchResult<PVLine>;
+}
+
+/// Iterative deepening negamax search.
+pub struct NegamaxSearcher {
+    config: SearchConfig,
+}
+
+impl NegamaxSearcher {
+    pub fn new(config: SearchConfig) -> Self {
+        NegamaxSearcher { config }
+    }
+}
+
+impl Searcher for NegamaxSearcher {
+    fn search(&mut self, game: &Game, verbose: bool) -> SearchResult<PVLine> {
+        negamax::search(self.config.clone(), game.clone(), verbose)
+    }
+}
+
+/// Creates the searcher selected in the search configuration. The negamax
+/// search is skill-limited if the configuration has a strength model.
+pub fn new_searcher(config: &SearchConfig) -> Box<dyn Searcher + Send> {
+    match config.searcher {
+        SearcherKind::Negamax => match &config.strength {
+            Some(strength) => Box::new(StrengthSearcher::new(config.clone(), strength.clone())),
+            None => Box::new(NegamaxSearcher::new(config.clone())),
+        },
+        SearcherKind::Mcts => Box::new(MctsSearcher::new(config.clone())),
+    }
+}
+
+/// Returns a move from the opening book or the tablebases if the position is
+/// covered by either of them.
+pub(crate) fn probe(config: &SearchConfig, game: &Game, verbose: bool) -> Option<PVLine> {
+    // Play from the opening book if the position is in it.
+    let book_line = config
+        .book
+        .as_ref()
+        .and_then(|book| book.probe(game))
+        .map(|m| PVLine {
+            moves: vec![m],
+            value: 0.0,
+        });
+    let (source, pvline) = match book_line {
+        Some(pvline) => ("Book move", pvline),
+        // Play the best tablebase move if the position is covered.
+        None => {
+            let tablebases = config.tablebases.as_ref()?;
+            ("Tablebase move", tablebase_line(tablebases, game)?)
+        }
+    };
+    if verbose {
+        display::clear_terminal();
+        display::print_game(game);
+        println!("{}", source);
+        display::print_pvlines(std::slice::from_ref(&pvline));
+    }
+    Some(pvline)
+}
+
+/// Follows the best tablebase moves from a position until the game ends.
+fn tablebase_line(tablebases: &Tablebases, game: &Game) -> Option<PVLine> {
+    let (_, outcome) = tablebases.best_move(game)?;
+    let mut pvline = PVLine {
+        moves: vec![],
+        value: outcome.score(),
+    };
+    let mut game = game.clone();
+    while let Some((m, _)) = tablebases.best_move(&game) {
+        // Drawn lines may never end.
+        if pvline.moves.len() >= u8::MAX as usize {
+            break;
+        }
+        pvline.moves.push(m);
+        game.take_turn(&m);
+    }
+    Some(pvline)
+}
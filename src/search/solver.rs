@@ -0,0 +1,281 @@
+//! Depth-first proof-number search for solving positions.
+//!
+//! Proof and disproof numbers are kept from the point of view of the player
+//! to move at each node: a proof number of zero means that the player to
+//! move wins, a disproof number of zero that they lose. Repeated positions
+//! count as a failure for the side that tries to prove its result, so a
+//! position is only solved if the win can be forced without repetitions.
+
+use crate::bao::game::Game;
+use crate::bao::move_factory::MoveFactory;
+use crate::bao::moves::Move;
+use crate::search::book::position_hash;
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::fmt::{Display, Formatter, Result};
+
+const INFINITY: u64 = 1 << 48;
+
+/// Main lines longer than this are cut off.
+const MAX_LINE_PLIES: usize = 256;
+
+/// The solved result for the player to move.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Proof {
+    Win,
+    Loss,
+    /// Not solved within the node budget, or drawn by repetition.
+    Unknown,
+}
+
+impl Display for Proof {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        match self {
+            Proof::Win => write!(f, "win"),
+            Proof::Loss => write!(f, "loss"),
+            Proof::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Solution {
+    pub proof: Proof,
+    /// The main line of the proof tree: the winning moves of the winner and
+    /// the longest resistance of the loser.
+    pub line: Vec<Move>,
+    /// The number of expanded nodes.
+    pub nodes: u64,
+}
+
+struct Solver {
+    tt: FxHashMap<u64, (u64, u64)>,
+    path: FxHashSet<u64>,
+    nodes: u64,
+    max_nodes: u64,
+    /// Whether repetitions count as a win for the root player.
+    repetition_wins: bool,
+}
+
+impl Solver {
+    fn new(max_nodes: u64, repetition_wins: bool) -> Solver {
+        Solver {
+            tt: FxHashMap::default(),
+            path: FxHashSet::default(),
+            nodes: 0,
+            max_nodes,
+            repetition_wins,
+        }
+    }
+
+    /// Returns the proof and disproof numbers of a position.
+    fn lookup(&self, game: &Game, hash: u64, ply: usize) -> (u64, u64) {
+        if self.path.contains(&hash) {
+            // The player to move wins the repetition if it is the root
+            // player and repetitions are wins, or vice versa.
+            let root_to_move = ply.is_multiple_of(2);
+            return match root_to_move == self.repetition_wins {
+                true => (0, INFINITY),
+                false => (INFINITY, 0),
+            };
+        }
+        if let Some(&numbers) = self.tt.get(&hash) {
+            return numbers;
+        }
+        match game.is_over() {
+            true => (INFINITY, 0),
+            false => (1, 1),
+        }
+    }
+
+    /// Expands a node until its proof number reaches `max_pn` or its
+    /// disproof number reaches `max_dn`.
+    fn mid(&mut self, game: &Game, hash: u64, ply: usize, max_pn: u64, max_dn: u64) {
+        self.nodes += 1;
+        if game.is_over() {
+            self.tt.insert(hash, (INFINITY, 0));
+            return;
+        }
+        let children: Vec<(Game, u64)> = MoveFactory::new(game)
+            .get_legal_moves()
+            .iter()
+            .map(|m| {
+                let mut child = game.clone();
+                child.take_turn(m);
+                let hash = position_hash(&child);
+                (child, hash)
+            })
+            .collect();
+
+        self.path.insert(hash);
+        let (mut pn, mut dn);
+        loop {
+            let numbers: Vec<(u64, u64)> = children
+                .iter()
+                .map(|(child, hash)| self.lookup(child, *hash, ply + 1))
+                .collect();
+            // We win if a child is lost for the opponent and lose if all are won.
+            pn = numbers.iter().map(|&(_, dn)| dn).min().unwrap_or(INFINITY);
+            dn = numbers
+                .iter()
+                .map(|&(pn, _)| pn)
+                .fold(0, |sum, pn| (sum + pn).min(INFINITY));
+            if pn >= max_pn || dn >= max_dn || self.nodes >= self.max_nodes {
+                break;
+            }
+
+            // Expand the most proving child.
+            let (best, second_dn) = most_proving(&numbers);
+            let (child_pn, _) = numbers[best];
+            let child_max_pn = match max_dn >= INFINITY {
+                true => INFINITY,
+                false => max_dn - dn + child_pn,
+            };
+            let child_max_dn = max_pn.min(second_dn.saturating_add(1));
+            let (child, child_hash) = &children[best];
+            self.mid(child, *child_hash, ply + 1, child_max_pn, child_max_dn);
+        }
+        self.path.remove(&hash);
+        self.tt.insert(hash, (pn, dn));
+    }
+
+    /// Follows the proof tree from the root.
+    fn main_line(&self, game: &Game) -> Vec<Move> {
+        let mut line = vec![];
+        let mut game = game.clone();
+        let mut visited = FxHashSet::default();
+        while line.len() < MAX_LINE_PLIES && !game.is_over() && visited.insert(position_hash(&game))
+        {
+            let mut factory = MoveFactory::new(&game);
+            let mut best: Option<(Move, Game, u64)> = None;
+            for m in factory.get_legal_moves() {
+                let mut child = game.clone();
+                child.take_turn(m);
+                let (pn, dn) = self
+                    .tt
+                    .get(&position_hash(&child))
+                    .copied()
+                    .unwrap_or(match child.is_over() {
+                        true => (INFINITY, 0),
+                        false => (1, 1),
+                    });
+                // Prefer children lost for the opponent, then the most resistant.
+                let key = match dn {
+                    0 => 0,
+                    _ => INFINITY - pn.min(INFINITY - 1),
+                };
+                if best.as_ref().is_none_or(|(_, _, k)| key < *k) {
+                    best = Some((*m, child, key));
+                }
+            }
+            match best {
+                Some((m, child, _)) => {
+                    line.push(m);
+                    game = child;
+                }
+                None => break,
+            }
+        }
+        line
+    }
+}
+
+/// Returns the child with the smallest disproof number and the second
+/// smallest disproof number.
+fn most_proving(numbers: &[(u64, u64)]) -> (usize, u64) {
+    let mut best = 0;
+    let mut second = INFINITY;
+    for (i, &(_, dn)) in numbers.iter().enumerate().skip(1) {
+        if dn < numbers[best].1 {
+            second = numbers[best].1;
+            best = i;
+        } else if dn < second {
+            second = dn;
+        }
+    }
+    (best, second)
+}
+
+/// Tries to solve a position for the player to move.
+///
+/// # Arguments
+///
+/// * `game` - The position to solve.
+/// * `max_nodes` - The maximum number of nodes to expand.
+///
+/// # Returns
+///
+/// The result for the player to move and the main line of the proof.
+pub fn solve(game: &Game, max_nodes: u64) -> Solution {
+    // First try to prove a win where repetitions count against us.
+    let root_hash = position_hash(game);
+    let mut solver = Solver::new(max_nodes, false);
+    solver.mid(game, root_hash, 0, INFINITY, INFINITY);
+    let mut nodes = solver.nodes;
+    let (pn, dn) = solver.tt[&root_hash];
+    let (proof, solver) = match (pn, dn) {
+        (0, _) => (Proof::Win, solver),
+        (_, 0) => {
+            // Then try to prove the loss where repetitions count for us.
+            let mut loss_solver = Solver::new(max_nodes.saturating_sub(nodes), true);
+            loss_solver.mid(game, root_hash, 0, INFINITY, INFINITY);
+            nodes += loss_solver.nodes;
+            match loss_solver.tt[&root_hash] {
+                (_, 0) => (Proof::Loss, loss_solver),
+                _ => (Proof::Unknown, loss_solver),
+            }
+        }
+        _ => (Proof::Unknown, solver),
+    };
+    let line = match proof {
+        Proof::Unknown => vec![],
+        _ => solver.main_line(game),
+    };
+    Solution { proof, line, nodes }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::bao::notation;
+    use crate::search::tablebase::{Outcome, Tablebases};
+
+    #[test]
+    fn test_solve_winning_capture() {
+        let game = notation::from_notation(
+            "0,1,0,0,0,0,0,0,0,0,0,0,0,0,3,0/0,0,0,0,0,0,2,0,2,0,0,0,0,0,0,0 0/0 00",
+        )
+        .unwrap();
+        let solution = solve(&game, 1000);
+        assert_eq!(solution.proof, Proof::Win);
+        assert_eq!(solution.line, vec![Move::mtaji_capture_right(14)]);
+    }
+
+    #[test]
+    fn test_solve_agrees_with_tablebase() {
+        let tablebases = Tablebases::generate(3).unwrap();
+        let positions = [
+            "0,2,0,0,0,0,0,0,0,0,0,0,0,0,0,0/0,0,1,0,0,0,0,0,0,0,0,0,0,0,0,0 0/0 00",
+            "2,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0/0,0,0,0,0,0,1,0,0,0,0,0,0,0,0,0 0/0 00",
+            "0,0,0,1,0,0,0,0,0,0,0,0,0,0,0,1/1,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0 0/0 00",
+            "0,0,0,0,0,0,0,3,0,0,0,0,0,0,0,0/0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0 0/0 00",
+        ];
+        for position in positions {
+            let game = notation::from_notation(position).unwrap();
+            let expected = match tablebases.probe(&game).unwrap() {
+                Outcome::Win(_) => Proof::Win,
+                Outcome::Loss(_) => Proof::Loss,
+                Outcome::Draw => Proof::Unknown,
+            };
+            assert_eq!(solve(&game, 100_000).proof, expected, "{}", position);
+        }
+    }
+
+    #[test]
+    fn test_solve_node_budget() {
+        let solution = solve(&Game::new(), 100);
+        assert_eq!(solution.proof, Proof::Unknown);
+        assert!(solution.line.is_empty());
+        assert!(solution.nodes <= 101);
+    }
+}
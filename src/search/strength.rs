@@ -0,0 +1,154 @@
+//! Skill-limited play for the lower difficulty levels.
+//!
+//! Instead of always playing the best move, a skill-limited searcher scores
+//! every root move with a shallow search and samples one of the best moves.
+//! Moves are weighted by a softmax over their score gap to the best move, so
+//! close alternatives are played often and clear mistakes rarely. Now and
+//! then the root scores are blurred with random noise, which leads to the
+//! occasional real blunder.
+
+use crate::bao::game::Game;
+use crate::bao::pv::PVLine;
+use crate::config::SearchConfig;
+use crate::error::SearchResult;
+use crate::search::analyze::{self, MoveAnalysis};
+use crate::search::searcher::{self, Searcher};
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::Rng;
+use std::sync::Arc;
+
+/// The strength model of a difficulty level.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Strength {
+    /// The number of best root moves to choose from.
+    pub top_k: usize,
+    /// The softmax temperature in evaluation units. Higher temperatures play
+    /// worse moves more often.
+    pub temperature: f32,
+    /// The probability to blur the root scores with noise.
+    pub noise_rate: f64,
+    /// The maximum noise added to a root score in evaluation units.
+    pub noise: f32,
+}
+
+impl Strength {
+    /// Chooses a move from the analysis of all root moves.
+    ///
+    /// # Arguments
+    ///
+    /// * `analysis` - The analysis of all root moves, sorted from best to worst.
+    /// * `rng` - The random number generator.
+    ///
+    /// # Returns
+    ///
+    /// The chosen move analysis, or none if no move was searched.
+    pub fn choose<'a, R: Rng>(
+        &self,
+        analysis: &'a [MoveAnalysis],
+        rng: &mut R,
+    ) -> Option<&'a MoveAnalysis> {
+        let mut scores: Vec<f32> = analysis.iter().map(|a| a.score).collect();
+        if rng.gen_bool(self.noise_rate.clamp(0.0, 1.0)) && self.noise > 0.0 {
+            for score in scores.iter_mut().filter(|s| s.is_finite()) {
+                *score += rng.gen_range(-self.noise..=self.noise);
+            }
+        }
+
+        // Keep the best searched moves after the noise.
+        let mut candidates: Vec<usize> = (0..analysis.len())
+            .filter(|&i| analysis[i].searched)
+            .collect();
+        candidates.sort_by(|&a, &b| scores[b].total_cmp(&scores[a]));
+        candidates.truncate(self.top_k.max(1));
+        let best = scores[*candidates.first()?];
+
+        let weights = candidates.iter().map(|&i| {
+            let gap = match scores[i] == best {
+                true => 0.0,
+                false => best - scores[i],
+            };
+            (-gap / self.temperature.max(f32::EPSILON)).exp()
+        });
+        let dist = WeightedIndex::new(weights).ok()?;
+        Some(&analysis[candidates[dist.sample(rng)]])
+    }
+}
+
+/// A searcher that plays with the strength model of the search configuration.
+/// Every root move is searched to the configured depth, so the depth limits
+/// the tactical vision.
+pub struct StrengthSearcher {
+    config: SearchConfig,
+    strength: Arc<Strength>,
+}
+
+impl StrengthSearcher {
+    pub fn new(config: SearchConfig, strength: Arc<Strength>) -> Self {
+        StrengthSearcher { config, strength }
+    }
+}
+
+impl Searcher for StrengthSearcher {
+    fn search(&mut self, game: &Game, verbose: bool) -> SearchResult<PVLine> {
+        if let Some(pvline) = searcher::probe(&self.config, game, verbose) {
+            return Ok(pvline);
+        }
+        let analysis = analyze::analyze(&self.config, game)?;
+        let chosen = self.strength.choose(&analysis, &mut rand::thread_rng());
+        Ok(chosen.map(|a| a.pv.clone()).unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::bao::moves::Move;
+
+    fn analysis(scores: &[f32]) -> Vec<MoveAnalysis> {
+        scores
+            .iter()
+            .enumerate()
+            .map(|(i, &score)| MoveAnalysis {
+                m: Move::namua_relay_left(i as i8),
+                score,
+                pv: PVLine::default(),
+                rank: i + 1,
+                searched: true,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_choose_top_k() {
+        let strength = Strength {
+            top_k: 2,
+            temperature: 1000.0,
+            noise_rate: 0.0,
+            noise: 0.0,
+        };
+        let analysis = analysis(&[3.0, 2.0, 1.0, f32::NEG_INFINITY]);
+        let mut rng = rand::thread_rng();
+        for _ in 0..100 {
+            let chosen = strength.choose(&analysis, &mut rng).expect("No move");
+            assert!(chosen.rank <= 2);
+        }
+    }
+
+    #[test]
+    fn test_choose_low_temperature_plays_best_move() {
+        let strength = Strength {
+            top_k: 4,
+            temperature: 0.01,
+            noise_rate: 0.0,
+            noise: 0.0,
+        };
+        let analysis = analysis(&[f32::INFINITY, f32::INFINITY, 1.0, 0.0]);
+        let mut rng = rand::thread_rng();
+        for _ in 0..100 {
+            let chosen = strength.choose(&analysis, &mut rng).expect("No move");
+            assert!(chosen.score.is_infinite());
+        }
+        assert!(strength.choose(&[], &mut rng).is_none());
+    }
+}
@@ -0,0 +1,440 @@
+//! Endgame tablebases for mtaji positions with few seeds.
+//!
+//! A tablebase covers every position with a given total number of seeds on
+//! the board, no seeds in hand and both nyumba gone. Captures only move seeds
+//! from one side of the board to the other, so such positions never leave
+//! their tablebase. Positions are indexed by ranking the 32 pit counts among
+//! all distributions of the seeds, and every position is stored in one byte:
+//! zero for a draw, otherwise the distance to the end of the game plus one.
+//! Odd distances are wins for the player to move, even distances are losses.
+
+use crate::bao::board::Mashumo;
+use crate::bao::game::Game;
+use crate::bao::move_factory::MoveFactory;
+use crate::bao::moves::Move;
+use crate::bao::player::Player;
+use crate::error::{TablebaseError, TablebaseResult};
+use std::fs;
+use std::path::Path;
+
+const MAGIC: &[u8; 8] = b"KIKTB001";
+const NUM_PITS: usize = 32;
+
+/// The largest number of seeds a tablebase can be generated for.
+pub const MAX_SEEDS: u8 = 8;
+
+/// The score of a won position at distance zero. Wins at larger distances
+/// score less, so the search prefers the fastest win and the slowest loss.
+pub const WIN_SCORE: f32 = 100_000.0;
+
+/// The tablebase result for the player to move.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Outcome {
+    /// Win in the given number of plies.
+    Win(u8),
+    /// Loss in the given number of plies.
+    Loss(u8),
+    /// Neither player can force a win.
+    Draw,
+}
+
+impl Outcome {
+    fn from_code(code: u8) -> Outcome {
+        match code {
+            0 => Outcome::Draw,
+            c if c % 2 == 0 => Outcome::Win(c - 1),
+            c => Outcome::Loss(c - 1),
+        }
+    }
+
+    /// Returns the search score of the outcome.
+    pub fn score(&self) -> f32 {
+        match self {
+            Outcome::Win(distance) => WIN_SCORE - *distance as f32,
+            Outcome::Loss(distance) => -WIN_SCORE + *distance as f32,
+            Outcome::Draw => 0.0,
+        }
+    }
+}
+
+/// The tablebase of all positions with a fixed number of seeds.
+#[derive(Clone, Debug)]
+pub struct Tablebase {
+    pub seeds: u8,
+    codes: Vec<u8>,
+}
+
+impl Tablebase {
+    /// Solves all positions with the given number of seeds by retrograde
+    /// analysis. Positions are resolved in rounds of increasing distance:
+    /// a position is won in `d` plies if a move leads to a loss in `d - 1`
+    /// plies, and lost in `d` plies if every move leads to a win in at most
+    /// `d - 1` plies. Positions that are still unresolved are draws.
+    ///
+    /// # Arguments
+    ///
+    /// * `seeds` - The total number of seeds on the board.
+    pub fn generate(seeds: u8) -> TablebaseResult<Tablebase> {
+        if seeds > MAX_SEEDS {
+            return Err(TablebaseError::TooManySeeds(seeds));
+        }
+        let size = num_positions(seeds);
+        let mut codes = vec![0u8; size];
+
+        // Collect the successors of every position.
+        let mut offsets = Vec::with_capacity(size + 1);
+        let mut children: Vec<u32> = vec![];
+        offsets.push(0);
+        for (index, code) in codes.iter_mut().enumerate() {
+            let game = unrank(index, seeds);
+            let mut factory = MoveFactory::new(&game);
+            let legal_moves = factory.get_legal_moves();
+            if game.current_player.mashumo.bitboard == 0 || legal_moves.is_empty() {
+                *code = 1; // Lost.
+            } else if game.other_player.mashumo.bitboard == 0 {
+                *code = 2; // Won, as if the other player had just lost.
+            } else {
+                for m in legal_moves {
+                    let mut child = game.clone();
+                    child.take_turn(m);
+                    children.push(rank(&child) as u32);
+                }
+            }
+            offsets.push(children.len());
+        }
+
+        // Resolve positions in rounds of increasing distance.
+        for distance in 1..u8::MAX - 1 {
+            let mut changed = false;
+            for index in 0..size {
+                if codes[index] != 0 {
+                    continue;
+                }
+                let mut all_won = true;
+                let mut win = false;
+                for &child in &children[offsets[index]..offsets[index + 1]] {
+                    match codes[child as usize] {
+                        c if c == 0 || c > distance => all_won = false,
+                        c if c % 2 == 1 && c == distance => win = true,
+                        c if c % 2 == 1 => all_won = false,
+                        _ => {}
+                    }
+                }
+                // Wins have odd distances and losses even ones.
+                let resolved = match distance % 2 {
+                    1 => win,
+                    _ => all_won,
+                };
+                if resolved {
+                    codes[index] = distance + 1;
+                    changed = true;
+                }
+            }
+            if !changed && distance % 2 == 0 {
+                break;
+            }
+        }
+        Ok(Tablebase { seeds, codes })
+    }
+
+    /// Returns the number of positions in the tablebase.
+    pub fn len(&self) -> usize {
+        self.codes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.codes.is_empty()
+    }
+
+    /// Returns the outcome of a position with the tablebase's number of seeds.
+    pub fn probe(&self, game: &Game) -> Outcome {
+        Outcome::from_code(self.codes[rank(game)])
+    }
+
+    /// Returns the number of won, lost and drawn positions.
+    pub fn stats(&self) -> (usize, usize, usize) {
+        let mut stats = (0, 0, 0);
+        for &code in &self.codes {
+            match Outcome::from_code(code) {
+                Outcome::Win(_) => stats.0 += 1,
+                Outcome::Loss(_) => stats.1 += 1,
+                Outcome::Draw => stats.2 += 1,
+            }
+        }
+        stats
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(MAGIC.len() + 1 + self.codes.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(self.seeds);
+        bytes.extend_from_slice(&self.codes);
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> TablebaseResult<Tablebase> {
+        if bytes.len() <= MAGIC.len() || !bytes.starts_with(MAGIC) {
+            return Err(TablebaseError::InvalidHeader);
+        }
+        let seeds = bytes[MAGIC.len()];
+        if seeds > MAX_SEEDS {
+            return Err(TablebaseError::TooManySeeds(seeds));
+        }
+        let codes = bytes[MAGIC.len() + 1..].to_vec();
+        if codes.len() != num_positions(seeds) {
+            return Err(TablebaseError::InvalidLength(bytes.len()));
+        }
+        Ok(Tablebase { seeds, codes })
+    }
+
+    /// Returns the file name of the tablebase with the given number of seeds.
+    pub fn file_name(seeds: u8) -> String {
+        format!("mtaji{}.tb", seeds)
+    }
+}
+
+/// A set of tablebases for all seed counts up to a maximum.
+#[derive(Clone, Debug, Default)]
+pub struct Tablebases {
+    tablebases: Vec<Tablebase>,
+}
+
+impl Tablebases {
+    /// Generates the tablebases for all seed counts up to `max_seeds`.
+    pub fn generate(max_seeds: u8) -> TablebaseResult<Tablebases> {
+        let tablebases = (0..=max_seeds)
+            .map(Tablebase::generate)
+            .collect::<TablebaseResult<_>>()?;
+        Ok(Tablebases { tablebases })
+    }
+
+    /// Loads the consecutive tablebases from a directory, starting at zero seeds.
+    pub fn load<P: AsRef<Path>>(dir: P) -> TablebaseResult<Tablebases> {
+        let mut tablebases = vec![];
+        for seeds in 0..=MAX_SEEDS {
+            let path = dir.as_ref().join(Tablebase::file_name(seeds));
+            if !path.exists() {
+                break;
+            }
+            let tablebase = Tablebase::from_bytes(&fs::read(&path)?)?;
+            // A renamed file would answer probes for the wrong seed count.
+            if tablebase.seeds != seeds {
+                return Err(TablebaseError::WrongSeeds(
+                    path.display().to_string(),
+                    tablebase.seeds,
+                ));
+            }
+            tablebases.push(tablebase);
+        }
+        if tablebases.is_empty() {
+            return Err(TablebaseError::NotFound(dir.as_ref().display().to_string()));
+        }
+        Ok(Tablebases { tablebases })
+    }
+
+    /// Saves every tablebase to its own file in a directory.
+    pub fn save<P: AsRef<Path>>(&self, dir: P) -> TablebaseResult<()> {
+        fs::create_dir_all(&dir)?;
+        for tablebase in &self.tablebases {
+            let path = dir.as_ref().join(Tablebase::file_name(tablebase.seeds));
+            fs::write(path, tablebase.to_bytes())?;
+        }
+        Ok(())
+    }
+
+    pub fn tablebases(&self) -> &[Tablebase] {
+        &self.tablebases
+    }
+
+    /// Returns the outcome of a position if it is covered by a tablebase.
+    pub fn probe(&self, game: &Game) -> Option<Outcome> {
+        let (current, other) = (&game.current_player, &game.other_player);
+        if current.seeds > 0 || other.seeds > 0 || current.nyumba || other.nyumba {
+            return None;
+        }
+        let seeds = total_seeds(game);
+        self.tablebases
+            .get(seeds as usize)
+            .map(|tablebase| tablebase.probe(game))
+    }
+
+    /// Returns the best move of a covered position and its outcome: the
+    /// fastest win, the slowest loss or a move that keeps the draw. Returns
+    /// None if the game is already over.
+    pub fn best_move(&self, game: &Game) -> Option<(Move, Outcome)> {
+        self.probe(game)?;
+        if game.is_over() || game.other_player.mashumo.bitboard == 0 {
+            return None;
+        }
+        let mut factory = MoveFactory::new(game);
+        let mut best: Option<(Move, Outcome)> = None;
+        for m in factory.get_legal_moves() {
+            let mut child = game.clone();
+            child.take_turn(m);
+            let outcome = match self.probe(&child)? {
+                Outcome::Win(d) => Outcome::Loss(d + 1),
+                Outcome::Loss(d) => Outcome::Win(d + 1),
+                Outcome::Draw => Outcome::Draw,
+            };
+            if best.is_none_or(|(_, b)| outcome.score() > b.score()) {
+                best = Some((*m, outcome));
+            }
+        }
+        best
+    }
+}
+
+fn total_seeds(game: &Game) -> u32 {
+    let current = game.current_player.mashumo.mashumo.iter();
+    let other = game.other_player.mashumo.mashumo.iter();
+    current.chain(other).map(|&s| s as u32).sum()
+}
+
+/// Returns the number of ways to distribute the seeds over the pits.
+fn num_distributions(seeds: usize, pits: usize) -> usize {
+    match pits {
+        0 => (seeds == 0) as usize,
+        _ => binomial(seeds + pits - 1, pits - 1),
+    }
+}
+
+fn binomial(n: usize, k: usize) -> usize {
+    (0..k.min(n - k)).fold(1, |acc, i| acc * (n - i) / (i + 1))
+}
+
+/// Returns the number of positions with the given number of seeds.
+pub fn num_positions(seeds: u8) -> usize {
+    num_distributions(seeds as usize, NUM_PITS)
+}
+
+fn pits(game: &Game) -> [u8; NUM_PITS] {
+    let mut pits = [0; NUM_PITS];
+    pits[..16].copy_from_slice(&game.current_player.mashumo.mashumo);
+    pits[16..].copy_from_slice(&game.other_player.mashumo.mashumo);
+    pits
+}
+
+/// Returns the index of a position among all positions with its number of seeds.
+fn rank(game: &Game) -> usize {
+    let pits = pits(game);
+    let mut remaining: usize = pits.iter().map(|&s| s as usize).sum();
+    let mut index = 0;
+    for (i, &seeds) in pits.iter().enumerate().take(NUM_PITS - 1) {
+        for k in 0..seeds as usize {
+            index += num_distributions(remaining - k, NUM_PITS - i - 1);
+        }
+        remaining -= seeds as usize;
+    }
+    index
+}
+
+/// Returns the position with the given index and number of seeds.
+fn unrank(mut index: usize, seeds: u8) -> Game {
+    let mut pits = [0u8; NUM_PITS];
+    let mut remaining = seeds as usize;
+    for (i, pit) in pits.iter_mut().enumerate().take(NUM_PITS - 1) {
+        let mut value = 0;
+        loop {
+            let count = num_distributions(remaining - value, NUM_PITS - i - 1);
+            if index < count {
+                break;
+            }
+            index -= count;
+            value += 1;
+        }
+        *pit = value as u8;
+        remaining -= value;
+    }
+    pits[NUM_PITS - 1] = remaining as u8;
+
+    let player = |seeds: &[u8]| Player {
+        mashumo: Mashumo::from_seeds(seeds.try_into().expect("16 pits")),
+        seeds: 0,
+        nyumba: false,
+    };
+    Game {
+        current_player: player(&pits[..16]),
+        other_player: player(&pits[16..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_rank_unrank() {
+        assert_eq!(num_positions(0), 1);
+        assert_eq!(num_positions(2), 528);
+        for index in 0..num_positions(2) {
+            assert_eq!(rank(&unrank(index, 2)), index);
+        }
+        let game = unrank(1234, 3);
+        assert_eq!(total_seeds(&game), 3);
+        assert_eq!(rank(&game), 1234);
+    }
+
+    #[test]
+    fn test_generate() -> TablebaseResult<()> {
+        let tablebases = Tablebases::generate(3)?;
+        // Without seeds the player to move has lost.
+        assert_eq!(tablebases.tablebases()[0].stats(), (0, 1, 0));
+
+        // The best move of every position leads to its outcome.
+        let tablebase = &tablebases.tablebases()[3];
+        let mut num_wins = 0;
+        for index in 0..tablebase.len() {
+            let game = unrank(index, 3);
+            let outcome = tablebase.probe(&game);
+            match tablebases.best_move(&game) {
+                Some((m, best)) => {
+                    assert_eq!(best, outcome);
+                    if outcome == Outcome::Win(1) {
+                        let mut after = game.clone();
+                        after.take_turn(&m);
+                        assert!(after.is_over());
+                        num_wins += 1;
+                    }
+                }
+                None => assert!(matches!(outcome, Outcome::Loss(0) | Outcome::Win(1))),
+            }
+        }
+        assert!(num_wins > 0);
+
+        // Positions with seeds in hand or a nyumba are not covered.
+        assert_eq!(tablebases.probe(&Game::new()), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_bytes_from_bytes() -> TablebaseResult<()> {
+        let tablebase = Tablebase::generate(2)?;
+        let bytes = tablebase.to_bytes();
+        assert_eq!(bytes.len(), 9 + 528);
+        let loaded = Tablebase::from_bytes(&bytes)?;
+        assert_eq!(loaded.codes, tablebase.codes);
+        assert!(matches!(
+            Tablebase::from_bytes(&bytes[..100]),
+            Err(TablebaseError::InvalidLength(100))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_rejects_mislabelled_file() -> TablebaseResult<()> {
+        let dir = std::env::temp_dir().join(format!("kikande-tablebase-{}", std::process::id()));
+        Tablebases::generate(1)?.save(&dir)?;
+        assert_eq!(Tablebases::load(&dir)?.tablebases().len(), 2);
+
+        // The file for one seed holds the tablebase for zero seeds.
+        fs::copy(
+            dir.join(Tablebase::file_name(0)),
+            dir.join(Tablebase::file_name(1)),
+        )?;
+        let result = Tablebases::load(&dir);
+        fs::remove_dir_all(&dir)?;
+        assert!(matches!(result, Err(TablebaseError::WrongSeeds(_, 0))));
+        Ok(())
+    }
+}
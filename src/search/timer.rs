@@ -1,12 +1,23 @@
 use std::cell::RefCell;
 use std::fmt::{Display, Formatter, Result};
-use std::time::Instant;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+/// The number of nodes between two checks of the time.
+const CHECK_INTERVAL: u32 = 1024;
+
+/// Stops the search when the time or the node budget is used up. Clones
+/// share the node count, so every thread of a search counts its nodes.
 #[derive(Clone)]
 pub struct Timer {
     start_time: Instant,
     max_time_ms: u32,
+    max_nodes: u64,
+    nodes: Arc<AtomicU64>,
     count: RefCell<u32>,
+    /// The nodes of this clone that are not yet added to the shared count.
+    pending: RefCell<u64>,
     is_time_up: RefCell<bool>,
 }
 
@@ -15,26 +26,65 @@ impl Timer {
         Timer {
             start_time: Instant::now(),
             max_time_ms,
+            max_nodes: u64::MAX,
+            nodes: Arc::new(AtomicU64::new(0)),
             count: RefCell::new(0),
+            pending: RefCell::new(0),
             is_time_up: RefCell::new(false),
         }
     }
 
+    /// Returns the timer with a maximum number of nodes.
+    pub fn with_max_nodes(mut self, max_nodes: Option<u64>) -> Self {
+        self.max_nodes = max_nodes.unwrap_or(u64::MAX);
+        self
+    }
+
+    /// Counts a node and returns whether the search has to stop.
     pub fn is_time_up(&self) -> bool {
         if *self.is_time_up.borrow() {
             return true;
         }
+        *self.pending.borrow_mut() += 1;
 
-        // Increment count wrapped around 1024.
-        self.count.replace_with(|&mut x| (x + 1) % 1024);
-        let count = self.count.borrow();
-        let is_time_up = match *count {
-            0 => self.start_time.elapsed().as_millis() as u32 >= self.max_time_ms,
-            _ => false,
+        // Increment count wrapped around the check interval.
+        self.count.replace_with(|&mut x| (x + 1) % CHECK_INTERVAL);
+        let is_check = *self.count.borrow() == 0;
+        // The threads share the node count, so only update it along with the
+        // time check, unless every node counts towards a node limit.
+        let has_node_limit = self.max_nodes != u64::MAX;
+        let is_time_up = match (is_check, has_node_limit) {
+            (true, _) => {
+                let nodes = self.flush();
+                self.start_time.elapsed().as_millis() as u32 >= self.max_time_ms
+                    || nodes > self.max_nodes
+            }
+            (false, true) => self.flush() > self.max_nodes,
+            (false, false) => false,
         };
         *self.is_time_up.borrow_mut() = is_time_up;
         is_time_up
     }
+
+    /// Adds the nodes counted since the last flush to the shared count.
+    ///
+    /// # Returns
+    ///
+    /// The shared count.
+    pub fn flush(&self) -> u64 {
+        let pending = self.pending.replace(0);
+        self.nodes.fetch_add(pending, Ordering::Relaxed) + pending
+    }
+
+    /// Returns the number of nodes counted by all clones up to their last
+    /// flush. Clones flush at least every 1024 nodes.
+    pub fn nodes(&self) -> u64 {
+        self.nodes.load(Ordering::Relaxed)
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.start_time.elapsed()
+    }
 }
 
 impl Display for Timer {
@@ -42,3 +92,29 @@ impl Display for Timer {
         write!(f, "Δt: {:?}", self.start_time.elapsed())
     }
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_timer_counts_nodes() {
+        let timer = Timer::new(u32::MAX);
+        let clone = timer.clone();
+        for _ in 0..CHECK_INTERVAL - 1 {
+            assert!(!clone.is_time_up());
+        }
+        // Without a node limit the shared count is updated along with the time check.
+        assert_eq!(timer.nodes(), 0);
+        assert!(!clone.is_time_up());
+        assert_eq!(timer.nodes(), CHECK_INTERVAL as u64);
+        assert!(!clone.is_time_up());
+        assert_eq!(clone.flush(), CHECK_INTERVAL as u64 + 1);
+
+        let timer = Timer::new(u32::MAX).with_max_nodes(Some(10));
+        let stopped = (0..20).position(|_| timer.is_time_up());
+        assert_eq!(stopped, Some(10));
+        assert_eq!(timer.nodes(), 11);
+    }
+}
@@ -0,0 +1,248 @@
+//! Search tree traces for debugging.
+//!
+//! A trace records the nodes visited by a negamax search down to a depth
+//! limit, together with the alpha-beta window on entry, the returned score,
+//! how the transposition table was used and which moves caused a cutoff.
+//! Traces can be exported as Graphviz DOT or as JSON.
+
+use crate::bao::game::Game;
+use crate::bao::moves::Move;
+use crate::bao::notation;
+use crate::search::transposition_table::EntryType;
+use serde::{Serialize, Serializer};
+use std::fmt::Write;
+
+/// How a node used the transposition table.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TtHit {
+    /// No entry was found.
+    #[default]
+    Miss,
+    /// The entry was too shallow, only its move was searched first.
+    Move,
+    /// The entry had an exact score.
+    Exact,
+    /// The entry had a lower bound.
+    Lower,
+    /// The entry had an upper bound.
+    Upper,
+}
+
+impl From<EntryType> for TtHit {
+    fn from(entry_type: EntryType) -> Self {
+        match entry_type {
+            EntryType::Exact => TtHit::Exact,
+            EntryType::LowerBound => TtHit::Lower,
+            EntryType::UpperBound => TtHit::Upper,
+        }
+    }
+}
+
+/// A node of the traced search tree.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct TraceNode {
+    /// The position in notation.
+    pub position: String,
+    /// The move leading to the node, none for the root.
+    #[serde(rename = "move")]
+    pub m: Option<Move>,
+    /// The remaining depth.
+    pub depth: u8,
+    #[serde(serialize_with = "serialize_score")]
+    pub alpha: f32,
+    #[serde(serialize_with = "serialize_score")]
+    pub beta: f32,
+    /// The score from the point of view of the player to move.
+    #[serde(serialize_with = "serialize_score")]
+    pub score: f32,
+    pub tt_hit: TtHit,
+    /// Whether the transposition table entry decided the score.
+    pub tt_cutoff: bool,
+    /// Whether the move leading to the node caused a beta cutoff.
+    pub cutoff: bool,
+    pub children: Vec<TraceNode>,
+}
+
+/// Serializes a score as a number, or as "inf" or "-inf" since JSON has no infinity.
+fn serialize_score<S: Serializer>(score: &f32, serializer: S) -> Result<S::Ok, S::Error> {
+    match score.is_infinite() {
+        true => serializer.serialize_str(&score.to_string()),
+        false => serializer.serialize_f32(*score),
+    }
+}
+
+/// Records the search tree. A disabled tracer records nothing.
+#[derive(Default)]
+pub struct Tracer {
+    max_depth: Option<usize>,
+    stack: Vec<TraceNode>,
+    ply: usize,
+    root: Option<TraceNode>,
+}
+
+impl Tracer {
+    /// Returns a tracer that records nothing.
+    pub fn disabled() -> Tracer {
+        Tracer::default()
+    }
+
+    /// Returns a tracer that records the nodes up to `max_depth` plies below the root.
+    pub fn new(max_depth: usize) -> Tracer {
+        Tracer {
+            max_depth: Some(max_depth),
+            ..Tracer::default()
+        }
+    }
+
+    fn is_recording(&self) -> bool {
+        self.max_depth
+            .is_some_and(|max_depth| self.ply <= max_depth)
+    }
+
+    /// Enters a node.
+    pub fn enter(&mut self, game: &Game, m: Option<Move>, depth: u8, alpha: f32, beta: f32) {
+        if self.is_recording() {
+            self.stack.push(TraceNode {
+                position: notation::to_notation(game),
+                m,
+                depth,
+                alpha,
+                beta,
+                ..TraceNode::default()
+            });
+        }
+        self.ply += 1;
+    }
+
+    /// Records how the current node used the transposition table.
+    pub fn tt_hit(&mut self, tt_hit: TtHit, tt_cutoff: bool) {
+        if let (true, Some(node)) = (self.is_parent_recording(), self.stack.last_mut()) {
+            node.tt_hit = tt_hit;
+            node.tt_cutoff = tt_cutoff;
+        }
+    }
+
+    /// Marks the last searched child of the current node as a cutoff.
+    pub fn cutoff(&mut self) {
+        if let (true, Some(node)) = (self.is_parent_recording(), self.stack.last_mut()) {
+            if let Some(child) = node.children.last_mut() {
+                child.cutoff = true;
+            }
+        }
+    }
+
+    /// Leaves the current node with its score.
+    pub fn exit(&mut self, score: f32) {
+        self.ply -= 1;
+        if !self.is_recording() {
+            return;
+        }
+        let mut node = self.stack.pop().expect("Tracer stack is empty");
+        node.score = score;
+        match self.stack.last_mut() {
+            Some(parent) => parent.children.push(node),
+            None => self.root = Some(node),
+        }
+    }
+
+    /// Whether the node entered last is recorded.
+    fn is_parent_recording(&self) -> bool {
+        self.max_depth
+            .is_some_and(|max_depth| self.ply > 0 && self.ply - 1 <= max_depth)
+    }
+
+    /// Returns the recorded tree, if a root node was recorded.
+    pub fn into_trace(self) -> Option<TraceNode> {
+        self.root
+    }
+}
+
+impl TraceNode {
+    /// Returns the number of nodes in the tree.
+    pub fn num_nodes(&self) -> usize {
+        1 + self
+            .children
+            .iter()
+            .map(TraceNode::num_nodes)
+            .sum::<usize>()
+    }
+
+    /// Writes the tree as a Graphviz DOT graph.
+    pub fn to_dot(&self) -> String {
+        let mut dot = "digraph search {\n    node [shape=box, fontname=monospace];\n".to_string();
+        let mut id = 0;
+        self.write_dot(&mut dot, &mut id);
+        dot.push_str("}\n");
+        dot
+    }
+
+    fn write_dot(&self, dot: &mut String, id: &mut usize) -> usize {
+        let node_id = *id;
+        *id += 1;
+        let label = format!(
+            "{}\\n{}\\nd={} α={:.2} β={:.2}\\nscore={:.2}\\ntt={:?}{}",
+            self.m.map_or("root".to_string(), |m| m.to_string()),
+            self.position,
+            self.depth,
+            self.alpha,
+            self.beta,
+            self.score,
+            self.tt_hit,
+            if self.tt_cutoff { " (cut)" } else { "" },
+        );
+        let color = if self.cutoff { ", color=red" } else { "" };
+        let _ = writeln!(dot, "    n{} [label=\"{}\"{}];", node_id, label, color);
+        for child in &self.children {
+            let child_id = child.write_dot(dot, id);
+            let style = if child.cutoff { " [color=red]" } else { "" };
+            let _ = writeln!(dot, "    n{} -> n{}{};", node_id, child_id, style);
+        }
+        node_id
+    }
+
+    /// Writes the tree as JSON.
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tracer_depth_limit() {
+        let game = Game::new();
+        let m = Move::namua_capture_left(5);
+        let mut tracer = Tracer::new(1);
+        tracer.enter(&game, None, 2, f32::NEG_INFINITY, 1.0);
+        tracer.tt_hit(TtHit::Move, false);
+        tracer.enter(&game, Some(m), 1, -1.0, 1.0);
+        tracer.enter(&game, Some(m), 0, -1.0, 1.0);
+        tracer.exit(0.5);
+        tracer.exit(-0.5);
+        tracer.cutoff();
+        tracer.exit(0.5);
+
+        let root = tracer.into_trace().expect("No root");
+        assert_eq!(root.num_nodes(), 2);
+        assert_eq!(root.tt_hit, TtHit::Move);
+        assert_eq!(root.children[0].m, Some(m));
+        assert!(root.children[0].cutoff);
+        assert!(root.children[0].children.is_empty());
+        assert!(root.to_dot().contains("n0 -> n1 [color=red];"));
+        assert!(root
+            .to_json()
+            .expect("Failed to serialize")
+            .contains("\"alpha\": \"-inf\""));
+    }
+
+    #[test]
+    fn test_disabled_tracer_records_nothing() {
+        let mut tracer = Tracer::disabled();
+        tracer.enter(&Game::new(), None, 1, -1.0, 1.0);
+        tracer.exit(0.0);
+        assert!(tracer.into_trace().is_none());
+    }
+}
@@ -0,0 +1,346 @@
+//! Texel-style tuning of the evaluation parameters.
+//!
+//! Every labelled position is first resolved with a quiescence search. The
+//! evaluation of the quiet leaf is mapped to an expected result with a sigmoid
+//! and the parameters are fitted by minimising the mean squared error between
+//! the expected and the actual results with a local search.
+
+use crate::bao::game::Game;
+use crate::bao::notation;
+use crate::error::{TuneError, TuneResult};
+use crate::search::eval_params::EvalParams;
+use crate::search::evaluate::{self, Feature, NUM_FEATURES};
+use crate::search::move_picker::MovePicker;
+use crate::search::node::Node;
+use crate::search::quiescence;
+use std::fs;
+use std::path::Path;
+
+/// A position labelled with the result of the game.
+#[derive(Clone, Debug)]
+pub struct LabelledPosition {
+    pub game: Game,
+    /// The result for the player to move: 1.0 for a win, 0.5 for a draw and
+    /// 0.0 for a loss.
+    pub result: f32,
+}
+
+/// Configuration of the tuner.
+#[derive(Clone, Debug)]
+pub struct TuneConfig {
+    /// The maximum number of passes over all parameters.
+    pub max_iterations: u32,
+    /// The initial step size of the local search.
+    pub step: f32,
+    /// The step size at which the local search stops.
+    pub min_step: f32,
+}
+
+impl Default for TuneConfig {
+    fn default() -> Self {
+        TuneConfig {
+            max_iterations: 100,
+            step: 0.1,
+            min_step: 0.01,
+        }
+    }
+}
+
+/// A quiet leaf reduced to its evaluation terms.
+struct TuningEntry {
+    /// Seed differences per pit from the point of view of the labelled player.
+    pits: [f32; 16],
+    /// Feature values from the point of view of the labelled player.
+    features: [f32; NUM_FEATURES],
+    result: f32,
+}
+
+impl TuningEntry {
+    fn evaluate(&self, params: &EvalParams) -> f32 {
+        let material: f32 = self
+            .pits
+            .iter()
+            .zip(params.pit_weights.iter())
+            .map(|(seeds, weight)| seeds * weight)
+            .sum();
+        let mut score = material * params.feature_weight(Feature::Material);
+        for (i, (feature, weight)) in self
+            .features
+            .iter()
+            .zip(params.feature_weights.iter())
+            .enumerate()
+        {
+            if i != Feature::Material as usize {
+                score += feature * weight;
+            }
+        }
+        score
+    }
+}
+
+/// Loads labelled positions from a file.
+///
+/// Every line holds a position in notation followed by the result for the
+/// player to move, e.g. `<position> 0.5`. Empty lines and lines starting with
+/// `#` are skipped.
+pub fn load_positions<P: AsRef<Path>>(path: P) -> TuneResult<Vec<LabelledPosition>> {
+    let mut positions = vec![];
+    for (i, line) in fs::read_to_string(path)?.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (position, result) = line
+            .rsplit_once(char::is_whitespace)
+            .ok_or_else(|| TuneError::InvalidLine(i + 1, line.to_string()))?;
+        let result: f32 = result
+            .parse()
+            .map_err(|_| TuneError::InvalidLine(i + 1, line.to_string()))?;
+        if !(0.0..=1.0).contains(&result) {
+            return Err(TuneError::InvalidLine(i + 1, line.to_string()));
+        }
+        positions.push(LabelledPosition {
+            game: notation::from_notation(position)?,
+            result,
+        });
+    }
+    Ok(positions)
+}
+
+/// Progress of the tuner, reported before the first and after every pass.
+#[derive(Clone, Copy, Debug)]
+pub struct TuneProgress {
+    /// The number of finished passes, zero before the first pass.
+    pub iteration: u32,
+    /// The number of positions that resolved to a quiet leaf.
+    pub positions: usize,
+    /// The scaling constant of the sigmoid.
+    pub k: f32,
+    /// The mean squared error of the best parameters so far.
+    pub error: f32,
+    /// The step size of the pass.
+    pub step: f32,
+}
+
+/// Tunes the evaluation parameters on labelled positions.
+///
+/// # Arguments
+///
+/// * `positions` - The labelled positions.
+/// * `params` - The parameters to start from.
+/// * `config` - The tuner configuration.
+///
+/// # Returns
+///
+/// The tuned parameters and their mean squared error.
+pub fn tune(
+    positions: &[LabelledPosition],
+    params: EvalParams,
+    config: &TuneConfig,
+) -> TuneResult<(EvalParams, f32)> {
+    tune_with_observer(positions, params, config, |_| {})
+}
+
+/// Tunes the evaluation parameters and reports the progress to an observer.
+///
+/// # Arguments
+///
+/// * `positions` - The labelled positions.
+/// * `params` - The parameters to start from.
+/// * `config` - The tuner configuration.
+/// * `observer` - Called with the progress before the first and after every pass.
+///
+/// # Returns
+///
+/// The tuned parameters and their mean squared error.
+pub fn tune_with_observer(
+    positions: &[LabelledPosition],
+    params: EvalParams,
+    config: &TuneConfig,
+    mut observer: impl FnMut(&TuneProgress),
+) -> TuneResult<(EvalParams, f32)> {
+    let entries = resolve(positions, &params);
+    if entries.is_empty() {
+        return Err(TuneError::NoPositions);
+    }
+
+    let k = fit_scaling_constant(&entries, &params);
+    let mut best_params = params;
+    let mut best_error = mean_squared_error(&entries, &best_params, k);
+    let mut step = config.step;
+    let mut progress = TuneProgress {
+        iteration: 0,
+        positions: entries.len(),
+        k,
+        error: best_error,
+        step,
+    };
+    observer(&progress);
+
+    for iteration in 0..config.max_iterations {
+        let mut improved = false;
+        for i in 0..num_tunable_params() {
+            for delta in [step, -step] {
+                let mut candidate = best_params.clone();
+                let param = tunable_param(&mut candidate, i);
+                // Round to keep the parameter file readable.
+                *param = ((*param + delta) * 1e4).round() / 1e4;
+                let error = mean_squared_error(&entries, &candidate, k);
+                if error < best_error {
+                    best_params = candidate;
+                    best_error = error;
+                    improved = true;
+                    break;
+                }
+            }
+        }
+        progress.iteration = iteration + 1;
+        progress.error = best_error;
+        progress.step = step;
+        observer(&progress);
+        if !improved {
+            step /= 2.0;
+            if step < config.min_step {
+                break;
+            }
+        }
+    }
+
+    Ok((best_params, best_error))
+}
+
+/// Resolves every position to its quiet leaf. Positions that resolve to the
+/// end of the game do not depend on the parameters and are skipped.
+fn resolve(positions: &[LabelledPosition], params: &EvalParams) -> Vec<TuningEntry> {
+    let mut entries = vec![];
+    for position in positions {
+        let node = Node::new(position.game.clone());
+        let (score, leaf, ply) = quiescence::quiesce(
+            node,
+            quiescence::MAX_DEPTH,
+            f32::NEG_INFINITY,
+            f32::INFINITY,
+            params,
+        );
+        if !score.is_finite() {
+            continue;
+        }
+
+        // Leaf values are from the point of view of the player to move at the leaf.
+        let sign = if ply % 2 == 0 { 1.0 } else { -1.0 };
+        let mut picker = MovePicker::new();
+        let legal_moves = picker.pick_moves(&leaf.game, None, params);
+        let features = evaluate::features(&leaf, legal_moves, params).map(|f| sign * f);
+        let mut pits = [0.0; 16];
+        for (i, pit) in pits.iter_mut().enumerate() {
+            let current = leaf.game.current_player.mashumo.get_seeds(i as i8) as f32;
+            let other = leaf.game.other_player.mashumo.get_seeds(i as i8) as f32;
+            *pit = sign * (current - other);
+        }
+        entries.push(TuningEntry {
+            pits,
+            features,
+            result: position.result,
+        });
+    }
+    entries
+}
+
+fn sigmoid(score: f32, k: f32) -> f32 {
+    1.0 / (1.0 + (-k * score).exp())
+}
+
+fn mean_squared_error(entries: &[TuningEntry], params: &EvalParams, k: f32) -> f32 {
+    let sum: f32 = entries
+        .iter()
+        .map(|entry| (entry.result - sigmoid(entry.evaluate(params), k)).powi(2))
+        .sum();
+    sum / entries.len() as f32
+}
+
+/// Finds the scaling constant of the sigmoid that best fits the results for
+/// the initial parameters with a golden section search on a log scale.
+fn fit_scaling_constant(entries: &[TuningEntry], params: &EvalParams) -> f32 {
+    let error = |log_k: f32| mean_squared_error(entries, params, 10f32.powf(log_k));
+    let ratio = (5f32.sqrt() - 1.0) / 2.0;
+    let (mut a, mut b) = (-4.0f32, 1.0f32);
+    for _ in 0..50 {
+        let c = b - ratio * (b - a);
+        let d = a + ratio * (b - a);
+        if error(c) < error(d) {
+            b = d;
+        } else {
+            a = c;
+        }
+    }
+    10f32.powf((a + b) / 2.0)
+}
+
+/// The material feature weight is fixed because it scales with the pit weights.
+fn num_tunable_params() -> usize {
+    16 + NUM_FEATURES - 1
+}
+
+fn tunable_param(params: &mut EvalParams, index: usize) -> &mut f32 {
+    if index < 16 {
+        return &mut params.pit_weights[index];
+    }
+    let mut feature = index - 16;
+    if feature >= Feature::Material as usize {
+        feature += 1;
+    }
+    &mut params.feature_weights[feature]
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::bao::game_builder::GameBuilder;
+
+    #[test]
+    fn test_tunable_params_skip_material() {
+        let mut params = EvalParams::default();
+        for i in 0..num_tunable_params() {
+            *tunable_param(&mut params, i) = 0.0;
+        }
+        assert_eq!(params.pit_weights, [0.0; 16]);
+        assert_eq!(params.feature_weight(Feature::Material), 1.0);
+        assert_eq!(params.feature_weight(Feature::SeedsInHand), 0.0);
+    }
+
+    #[test]
+    fn test_tune_does_not_increase_error() -> TuneResult<()> {
+        let positions = vec![
+            LabelledPosition {
+                game: bao_game!("6L", "5R"),
+                result: 0.0,
+            },
+            LabelledPosition {
+                game: bao_game!("6L", "5R", "3L"),
+                result: 1.0,
+            },
+            LabelledPosition {
+                game: Game::new(),
+                result: 0.5,
+            },
+        ];
+        let params = EvalParams::default();
+        let entries = resolve(&positions, &params);
+        let k = fit_scaling_constant(&entries, &params);
+        let initial_error = mean_squared_error(&entries, &params, k);
+        let config = TuneConfig {
+            max_iterations: 3,
+            ..TuneConfig::default()
+        };
+        let mut reported = vec![];
+        let (_, error) = tune_with_observer(&positions, params, &config, |progress| {
+            reported.push((progress.iteration, progress.error))
+        })?;
+        assert!(error <= initial_error);
+        assert_eq!(reported.first(), Some(&(0, initial_error)));
+        assert_eq!(reported.last().map(|&(_, error)| error), Some(error));
+        assert!(reported.windows(2).all(|w| w[1].1 <= w[0].1));
+        Ok(())
+    }
+}
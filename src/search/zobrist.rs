@@ -1,5 +1,8 @@
+//! Zobrist hashing of positions.
+
 use crate::bao::game::Game;
 
+/// Returns the zobrist hash of a position, as used by the transposition table.
 #[inline]
 pub fn zobrist(game: &Game) -> u64 {
     let mut hash = game.current_player.mashumo.zobrist;
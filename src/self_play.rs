@@ -0,0 +1,627 @@
+//! Self-play matches between two engine configurations.
+
+use crate::bao::game::Game;
+use crate::bao::move_factory::MoveFactory;
+use crate::bao::moves::Move;
+use crate::bao::notation;
+use crate::bao::record::{GameRecord, GameResult};
+use crate::config::SearchConfig;
+use crate::error::{MatchError, MatchResult, SearchError, SearchResult};
+use crate::search::book::Book;
+use crate::search::eval_params::EvalParams;
+use crate::search::searcher::{self, Searcher};
+use crate::search::tablebase::Tablebases;
+use rand::seq::SliceRandom;
+use std::fmt::{Display, Formatter, Result};
+use std::str::FromStr;
+
+/// An engine configuration taking part in a match.
+#[derive(Clone, Debug)]
+pub struct Engine {
+    pub name: String,
+    pub config: SearchConfig,
+}
+
+impl FromStr for Engine {
+    type Err = MatchError;
+
+    /// Parses an engine from comma separated `key=value` pairs, e.g.
+    /// `depth=8,time=100,nodes=100000,threads=1,eval=params.txt,quiescence=on,ordering=off`.
+    /// An opening book and tablebases are added with `book=book.bin` and
+    /// `tb=tablebases`. Monte Carlo tree search is selected with
+    /// `searcher=mcts,iterations=20000,exploration=1.4,playout=heuristic`.
+    /// `level=3` plays like difficulty level 3 of the play mode.
+    fn from_str(s: &str) -> MatchResult<Engine> {
+        let mut config = SearchConfig::new(20, 1, None);
+        let invalid = |entry: &str| MatchError::InvalidEngine(entry.to_string());
+        for entry in s.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (key, value) = entry.split_once('=').ok_or_else(|| invalid(entry))?;
+            match key.trim() {
+                "level" => {
+                    config = config.with_difficulty(value.parse().map_err(|_| invalid(entry))?)
+                }
+                "depth" => config.max_depth = value.parse().map_err(|_| invalid(entry))?,
+                "time" => config.max_time_ms = Some(value.parse().map_err(|_| invalid(entry))?),
+                "nodes" => config.max_nodes = Some(value.parse().map_err(|_| invalid(entry))?),
+                "threads" => config.num_threads = value.parse().map_err(|_| invalid(entry))?,
+                "eval" => config = config.with_eval_params(EvalParams::load(value.trim())?),
+                "book" => config = config.with_book(Book::load(value.trim())?),
+                "tb" => config = config.with_tablebases(Tablebases::load(value.trim())?),
+                "searcher" => config.searcher = value.parse().map_err(|_| invalid(entry))?,
+                "iterations" => {
+                    config.mcts.iterations = value.parse().map_err(|_| invalid(entry))?
+                }
+                "exploration" => {
+                    config.mcts.exploration = value.parse().map_err(|_| invalid(entry))?
+                }
+                "playout" => config.mcts.playout = value.parse().map_err(|_| invalid(entry))?,
+                "quiescence" => {
+                    config.quiescence = parse_toggle(value).ok_or_else(|| invalid(entry))?
+                }
+                "ordering" => {
+                    config.move_ordering = parse_toggle(value).ok_or_else(|| invalid(entry))?
+                }
+                _ => return Err(invalid(entry)),
+            }
+        }
+        Ok(Engine {
+            name: s.to_string(),
+            config,
+        })
+    }
+}
+
+fn parse_toggle(value: &str) -> Option<bool> {
+    match value.trim() {
+        "on" | "true" => Some(true),
+        "off" | "false" => Some(false),
+        _ => None,
+    }
+}
+
+/// Configuration of the sequential probability ratio test.
+#[derive(Clone, Debug)]
+pub struct SprtConfig {
+    /// Elo difference of the null hypothesis.
+    pub elo0: f64,
+    /// Elo difference of the alternative hypothesis.
+    pub elo1: f64,
+    /// Probability of a false positive.
+    pub alpha: f64,
+    /// Probability of a false negative.
+    pub beta: f64,
+}
+
+impl Default for SprtConfig {
+    fn default() -> Self {
+        SprtConfig {
+            elo0: 0.0,
+            elo1: 10.0,
+            alpha: 0.05,
+            beta: 0.05,
+        }
+    }
+}
+
+/// Verdict of the sequential probability ratio test.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SprtVerdict {
+    /// H0: Elo diff ≤ elo0 accepted.
+    AcceptH0,
+    /// H1: Elo diff ≥ elo1 accepted.
+    AcceptH1,
+    /// More games are needed.
+    Continue,
+}
+
+/// Configuration of a match.
+#[derive(Clone, Debug)]
+pub struct MatchConfig {
+    /// The number of games to play.
+    pub num_games: u32,
+    /// Openings as strings of moves. Used in turn if not empty.
+    pub openings: Vec<String>,
+    /// The number of random plies to play if there are no openings.
+    pub random_plies: u8,
+    /// Games that are not decided after this many plies are drawn.
+    pub max_plies: u32,
+    pub sprt: SprtConfig,
+}
+
+impl Default for MatchConfig {
+    fn default() -> Self {
+        MatchConfig {
+            num_games: 100,
+            openings: vec![],
+            random_plies: 4,
+            max_plies: 400,
+            sprt: SprtConfig::default(),
+        }
+    }
+}
+
+/// Wins, losses and draws from the point of view of the first engine.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MatchStats {
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+}
+
+impl MatchStats {
+    pub fn num_games(&self) -> u32 {
+        self.wins + self.losses + self.draws
+    }
+
+    /// Returns the mean score per game.
+    pub fn score(&self) -> f64 {
+        (self.wins as f64 + 0.5 * self.draws as f64) / self.num_games().max(1) as f64
+    }
+
+    /// Returns the variance of the score per game.
+    fn variance(&self) -> f64 {
+        let n = self.num_games().max(1) as f64;
+        let s = self.score();
+        (self.wins as f64 * (1.0 - s).powi(2)
+            + self.losses as f64 * s.powi(2)
+            + self.draws as f64 * (0.5 - s).powi(2))
+            / n
+    }
+
+    /// Returns the Elo difference and its 95% confidence interval.
+    pub fn elo(&self) -> (f64, f64) {
+        let n = self.num_games().max(1) as f64;
+        let s = self.score();
+        let margin = 1.96 * (self.variance() / n).sqrt();
+        let low = score_to_elo(s - margin);
+        let high = score_to_elo(s + margin);
+        (score_to_elo(s), (high - low) / 2.0)
+    }
+
+    /// Returns the log-likelihood ratio of the two hypotheses. It is zero as
+    /// long as all games had the same result.
+    pub fn llr(&self, sprt: &SprtConfig) -> f64 {
+        let variance = self.variance();
+        if self.num_games() == 0 || variance == 0.0 {
+            return 0.0;
+        }
+        let s0 = elo_to_score(sprt.elo0);
+        let s1 = elo_to_score(sprt.elo1);
+        let n = self.num_games() as f64;
+        n * (s1 - s0) * (2.0 * self.score() - s0 - s1) / (2.0 * variance)
+    }
+
+    /// Returns the verdict of the sequential probability ratio test.
+    pub fn sprt(&self, sprt: &SprtConfig) -> SprtVerdict {
+        let llr = self.llr(sprt);
+        let (lower, upper) = sprt_bounds(sprt);
+        if llr >= upper {
+            SprtVerdict::AcceptH1
+        } else if llr <= lower {
+            SprtVerdict::AcceptH0
+        } else {
+            SprtVerdict::Continue
+        }
+    }
+
+    fn add(&mut self, score: f64) {
+        match score {
+            s if s > 0.5 => self.wins += 1,
+            s if s < 0.5 => self.losses += 1,
+            _ => self.draws += 1,
+        }
+    }
+}
+
+impl Display for MatchStats {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        let (elo, error) = self.elo();
+        write!(
+            f,
+            "W/L/D: {}/{}/{}, score: {:.1}%, Elo: {:+.1} ± {:.1}",
+            self.wins,
+            self.losses,
+            self.draws,
+            100.0 * self.score(),
+            elo,
+            error
+        )
+    }
+}
+
+/// Returns the lower and upper bounds of the log-likelihood ratio.
+pub fn sprt_bounds(sprt: &SprtConfig) -> (f64, f64) {
+    (
+        (sprt.beta / (1.0 - sprt.alpha)).ln(),
+        ((1.0 - sprt.beta) / sprt.alpha).ln(),
+    )
+}
+
+fn elo_to_score(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+fn score_to_elo(score: f64) -> f64 {
+    let score = score.clamp(1e-6, 1.0 - 1e-6);
+    -400.0 * (1.0 / score - 1.0).log10()
+}
+
+/// Progress of a match, reported after every game.
+pub struct MatchProgress<'a> {
+    /// The number of the game, starting at 1.
+    pub round: u32,
+    /// The record of the game.
+    pub record: &'a GameRecord,
+    /// The statistics of all games so far.
+    pub stats: &'a MatchStats,
+}
+
+/// Plays a match between two engines. The engines alternate who starts and
+/// play every opening once from each side. The match ends early once the
+/// sequential probability ratio test accepts a hypothesis.
+///
+/// # Arguments
+///
+/// * `first` - The engine whose point of view the statistics take.
+/// * `second` - The opponent.
+/// * `config` - The match configuration.
+///
+/// # Returns
+///
+/// The match statistics and the records of all games.
+pub fn play_match(
+    first: &Engine,
+    second: &Engine,
+    config: &MatchConfig,
+) -> MatchResult<(MatchStats, Vec<GameRecord>)> {
+    play_match_with_observer(first, second, config, |_| {})
+}
+
+/// Plays a match between two engines and reports the progress to an
+/// observer, see `play_match`.
+///
+/// # Arguments
+///
+/// * `first` - The engine whose point of view the statistics take.
+/// * `second` - The opponent.
+/// * `config` - The match configuration.
+/// * `observer` - Called with the progress after every game.
+///
+/// # Returns
+///
+/// The match statistics and the records of all games.
+pub fn play_match_with_observer(
+    first: &Engine,
+    second: &Engine,
+    config: &MatchConfig,
+    mut observer: impl FnMut(&MatchProgress),
+) -> MatchResult<(MatchStats, Vec<GameRecord>)> {
+    // Parse the openings first, so that a bad one fails before any game is played.
+    let openings = config
+        .openings
+        .iter()
+        .enumerate()
+        .map(
+            |(index, opening)| match GameRecord::from_moves_str(opening) {
+                Ok(record) => Ok(record.moves),
+                Err(e) => Err(MatchError::InvalidOpening(index + 1, e)),
+            },
+        )
+        .collect::<MatchResult<Vec<_>>>()?;
+    let mut stats = MatchStats::default();
+    let mut records = vec![];
+    let mut opening = vec![];
+    for round in 0..config.num_games {
+        if round % 2 == 0 {
+            opening = match openings.is_empty() {
+                true => random_opening(config.random_plies),
+                false => {
+                    let index = (round as usize / 2) % openings.len();
+                    openings[index].clone()
+                }
+            };
+        }
+
+        // Alternate who starts.
+        let (starter, other) = match round % 2 {
+            0 => (first, second),
+            _ => (second, first),
+        };
+        let mut record = play_game(&opening, &starter.config, &other.config, config.max_plies)?;
+        record.set_tag("Event", "kikande match");
+        record.set_tag("Round", &(round + 1).to_string());
+        record.set_tag("First", &starter.name);
+        record.set_tag("Second", &other.name);
+
+        let score = match (record.result, round % 2) {
+            (GameResult::FirstPlayerWins, 0) | (GameResult::SecondPlayerWins, 1) => 1.0,
+            (GameResult::FirstPlayerWins, _) | (GameResult::SecondPlayerWins, _) => 0.0,
+            _ => 0.5,
+        };
+        stats.add(score);
+        observer(&MatchProgress {
+            round: round + 1,
+            record: &record,
+            stats: &stats,
+        });
+        records.push(record);
+        if stats.sprt(&config.sprt) != SprtVerdict::Continue {
+            break;
+        }
+    }
+    Ok((stats, records))
+}
+
+/// Plays a single game from an opening.
+///
+/// # Arguments
+///
+/// * `opening` - The opening moves played from the usual start.
+/// * `first` - The configuration of the engine that makes the first move.
+/// * `second` - The configuration of the other engine.
+/// * `max_plies` - Games that are not decided after this many plies are drawn.
+pub fn play_game(
+    opening: &[Move],
+    first: &SearchConfig,
+    second: &SearchConfig,
+    max_plies: u32,
+) -> MatchResult<GameRecord> {
+    let mut record = GameRecord::default();
+    let mut game = Game::new();
+    let mut searchers = [
+        searcher::new_searcher(first),
+        searcher::new_searcher(second),
+    ];
+    for m in opening {
+        game.take_turn(m);
+        record.moves.push(*m);
+    }
+
+    while (record.moves.len() as u32) < max_plies {
+        let first_to_move = record.moves.len() % 2 == 0;
+        if game.is_over() {
+            record.result = match first_to_move {
+                true => GameResult::SecondPlayerWins,
+                false => GameResult::FirstPlayerWins,
+            };
+            return Ok(record);
+        }
+        let searcher = &mut searchers[record.moves.len() % 2];
+        let m = engine_move(&game, searcher.as_mut())?;
+        game.take_turn(&m);
+        record.moves.push(m);
+    }
+    record.result = GameResult::Draw;
+    Ok(record)
+}
+
+/// Searches for the engine's move. Falls back to the first legal move if the
+/// search does not return a line, e.g. because every move loses.
+///
+/// # Returns
+///
+/// An error if the search returns an illegal move.
+pub fn engine_move(game: &Game, searcher: &mut dyn Searcher) -> SearchResult<Move> {
+    let pvline = searcher.search(game, false)?;
+    let mut factory = MoveFactory::new(game);
+    let legal_moves = factory.get_legal_moves();
+    match pvline.moves.first() {
+        Some(m) if legal_moves.contains(m) => Ok(*m),
+        Some(m) => Err(SearchError::IllegalMove(format!(
+            "{} in {}",
+            m,
+            notation::to_notation(game)
+        ))),
+        None => Ok(legal_moves[0]),
+    }
+}
+
+/// Plays random legal moves from the usual start without ending the game.
+pub fn random_opening(num_plies: u8) -> Vec<Move> {
+    let mut rng = rand::thread_rng();
+    loop {
+        let mut game = Game::new();
+        let mut moves = vec![];
+        while moves.len() < num_plies as usize && !game.is_over() {
+            let mut factory = MoveFactory::new(&game);
+            let m = *factory
+                .get_legal_moves()
+                .choose(&mut rng)
+                .expect("No legal moves");
+            game.take_turn(&m);
+            moves.push(m);
+        }
+        if !game.is_over() {
+            return moves;
+        }
+    }
+}
+
+/// Returns every position of the decided games labelled with the result for
+/// the player to move, in the format read by the tuner.
+pub fn labelled_positions(records: &[GameRecord]) -> MatchResult<Vec<String>> {
+    let mut positions = vec![];
+    for record in records {
+        let first_score = match record.result {
+            GameResult::FirstPlayerWins => 1.0,
+            GameResult::SecondPlayerWins => 0.0,
+            GameResult::Draw => 0.5,
+            GameResult::Unknown => continue,
+        };
+        let games = record.games()?;
+        for (ply, game) in games.iter().enumerate().take(record.moves.len()) {
+            let result = match ply % 2 {
+                0 => first_score,
+                _ => 1.0 - first_score,
+            };
+            positions.push(format!("{} {}", notation::to_notation(game), result));
+        }
+    }
+    Ok(positions)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::bao::pv::PVLine;
+    use crate::config::SearcherKind;
+    use crate::search::mcts::Playout;
+
+    #[test]
+    fn test_engine_from_str() -> MatchResult<()> {
+        let engine: Engine = "depth=6,time=100,threads=2,quiescence=on,ordering=off".parse()?;
+        assert_eq!(engine.config.max_depth, 6);
+        assert_eq!(engine.config.max_time_ms, Some(100));
+        assert_eq!(engine.config.num_threads, 2);
+        assert!(engine.config.quiescence);
+        assert!(!engine.config.move_ordering);
+        let engine: Engine = "searcher=mcts,iterations=500,playout=heuristic".parse()?;
+        assert_eq!(engine.config.searcher, SearcherKind::Mcts);
+        assert_eq!(engine.config.mcts.iterations, 500);
+        assert_eq!(engine.config.mcts.playout, Playout::Heuristic);
+        let engine: Engine = "level=3,threads=2".parse()?;
+        assert_eq!(engine.config.max_depth, 2);
+        assert_eq!(engine.config.num_threads, 2);
+        assert!(engine.config.strength.is_some());
+        assert!("level=10".parse::<Engine>()?.config.max_depth > 2);
+        match "depth=x".parse::<Engine>() {
+            Err(e) => assert_eq!(e.to_string(), "InvalidEngine: depth=x"),
+            _ => panic!(),
+        }
+        match "speed=3".parse::<Engine>() {
+            Err(e) => assert_eq!(e.to_string(), "InvalidEngine: speed=3"),
+            _ => panic!(),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_match_stats_elo() {
+        let even = MatchStats {
+            wins: 10,
+            losses: 10,
+            draws: 0,
+        };
+        let (elo, error) = even.elo();
+        assert!(elo.abs() < 1e-6);
+        assert!(error > 0.0);
+
+        // A 75% score is about +191 Elo.
+        let stronger = MatchStats {
+            wins: 15,
+            losses: 5,
+            draws: 0,
+        };
+        let (elo, _) = stronger.elo();
+        assert!((elo - 190.8).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_match_stats_sprt() {
+        let sprt = SprtConfig::default();
+        let stats = MatchStats {
+            wins: 10,
+            losses: 10,
+            draws: 0,
+        };
+        assert_eq!(stats.sprt(&sprt), SprtVerdict::Continue);
+        let stats = MatchStats {
+            wins: 400,
+            losses: 200,
+            draws: 0,
+        };
+        assert_eq!(stats.sprt(&sprt), SprtVerdict::AcceptH1);
+        let stats = MatchStats {
+            wins: 200,
+            losses: 400,
+            draws: 0,
+        };
+        assert_eq!(stats.sprt(&sprt), SprtVerdict::AcceptH0);
+    }
+
+    #[test]
+    fn test_play_match_stops_on_verdict() -> MatchResult<()> {
+        let engine: Engine = "depth=1".parse()?;
+        // With these error rates both bounds are zero, so the first game decides.
+        let config = MatchConfig {
+            num_games: 4,
+            max_plies: 20,
+            sprt: SprtConfig {
+                alpha: 0.5,
+                beta: 0.5,
+                ..SprtConfig::default()
+            },
+            ..MatchConfig::default()
+        };
+        let mut rounds = vec![];
+        let (stats, records) = play_match_with_observer(&engine, &engine, &config, |progress| {
+            rounds.push(progress.round)
+        })?;
+        assert_eq!(rounds, [1]);
+        assert_eq!((stats.num_games(), records.len()), (1, 1));
+        Ok(())
+    }
+
+    #[test]
+    fn test_play_match_rejects_bad_opening() -> MatchResult<()> {
+        let engine: Engine = "depth=1".parse()?;
+        let config = MatchConfig {
+            num_games: 4,
+            openings: vec!["6L 5R".to_string(), "6L 5R; 3l".to_string()],
+            ..MatchConfig::default()
+        };
+        let mut rounds = 0;
+        match play_match_with_observer(&engine, &engine, &config, |_| rounds += 1) {
+            Err(e) => assert!(e.to_string().starts_with("InvalidOpening: 2: ")),
+            Ok(_) => panic!(),
+        }
+        assert_eq!(rounds, 0);
+        Ok(())
+    }
+
+    /// A searcher that always returns the same line.
+    struct FixedSearcher(Vec<Move>);
+
+    impl Searcher for FixedSearcher {
+        fn search(&mut self, _game: &Game, _verbose: bool) -> SearchResult<PVLine> {
+            Ok(PVLine {
+                moves: self.0.clone(),
+                value: 0.0,
+            })
+        }
+    }
+
+    #[test]
+    fn test_engine_move() -> SearchResult<()> {
+        let game = Game::new();
+        let legal_move = Move::namua_relay_left(5);
+        assert_eq!(
+            engine_move(&game, &mut FixedSearcher(vec![legal_move]))?,
+            legal_move
+        );
+        let first_legal_move = MoveFactory::new(&game).get_legal_moves()[0];
+        assert_eq!(
+            engine_move(&game, &mut FixedSearcher(vec![]))?,
+            first_legal_move
+        );
+        assert!(matches!(
+            engine_move(&game, &mut FixedSearcher(vec![Move::mtaji_relay_left(12)])),
+            Err(SearchError::IllegalMove(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_play_game() -> MatchResult<()> {
+        let config = SearchConfig::new(1, 1, None);
+        let record = play_game(&random_opening(2), &config, &config, 400)?;
+        assert_ne!(record.result, GameResult::Unknown);
+        let positions = labelled_positions(std::slice::from_ref(&record))?;
+        match record.result {
+            GameResult::Draw => assert_eq!(positions.len(), 400),
+            _ => assert_eq!(positions.len(), record.moves.len()),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,321 @@
+//! Interactive analysis shell.
+//!
+//! The shell keeps a current position and the moves played from its starting
+//! position. Every command is a thin wrapper over the library.
+
+use crate::bao::game::Game;
+use crate::bao::move_factory::MoveFactory;
+use crate::bao::moves::Move;
+use crate::bao::notation;
+use crate::bao::perft;
+use crate::bao::record;
+use crate::config::SearchConfig;
+use crate::error::ShellResult;
+use crate::play;
+use crate::render::terminal::BoardRenderer;
+use crate::search::analyze;
+use crate::search::negamax;
+use crate::search::zobrist;
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+use std::time::Instant;
+
+const HELP: &str = "\
+new                 Start a new game.
+position <notation> Set up a position.
+move <moves>        Play moves, e.g. move 6L 5R; 3L.
+undo                Take back the last move.
+moves               List the legal moves.
+show                Show the board.
+eval                Show the static evaluation.
+go [depth N]        Search for the best move.
+analyze             Search every legal move.
+perft N             Count the positions N turns ahead after every move.
+hash                Show the zobrist hash.
+notation            Show the position in notation.
+history             Show the moves played.
+help                Show this help.
+quit                Leave the shell.";
+
+/// The state of the shell.
+pub struct Shell {
+    config: SearchConfig,
+    /// The starting position in notation, or none for the usual start.
+    start: Option<String>,
+    moves: Vec<Move>,
+    game: Game,
+}
+
+impl Shell {
+    /// Creates a shell in the starting position.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The search configuration of `go` and `analyze`.
+    pub fn new(config: SearchConfig) -> Shell {
+        Shell {
+            config,
+            start: None,
+            moves: vec![],
+            game: Game::new(),
+        }
+    }
+
+    /// Returns the current position.
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// Runs a command.
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - The command line.
+    ///
+    /// # Returns
+    ///
+    /// The output of the command, or a message explaining why it failed.
+    pub fn execute(&mut self, line: &str) -> Result<String, String> {
+        let line = line.trim();
+        let (command, argument) = match line.split_once(char::is_whitespace) {
+            Some((command, argument)) => (command, argument.trim()),
+            None => (line, ""),
+        };
+        let no_argument = || match argument {
+            "" => Ok(()),
+            _ => Err(format!("{} takes no argument: {}", command, argument)),
+        };
+        match command {
+            "new" => {
+                no_argument()?;
+                self.start = None;
+                self.moves.clear();
+                self.game = Game::new();
+                Ok("New game.".to_string())
+            }
+            "position" => self.position(argument),
+            "move" => self.play(argument),
+            "undo" => {
+                no_argument()?;
+                let m = self.moves.pop().ok_or("There is no move to take back.")?;
+                self.game = self.replay(&self.moves)?;
+                Ok(format!("Took back {}.", m))
+            }
+            "moves" => {
+                no_argument()?;
+                let moves: Vec<String> = MoveFactory::new(&self.game)
+                    .get_legal_moves()
+                    .iter()
+                    .map(|m| m.to_string())
+                    .collect();
+                Ok(moves.join(" "))
+            }
+            "show" => {
+                no_argument()?;
+                Ok(BoardRenderer::new().render_game(&self.game))
+            }
+            "eval" => {
+                no_argument()?;
+                let score = analyze::static_evaluation(&self.game, &self.config.eval_params);
+                Ok(format!("{:+.2} for the player to move", score))
+            }
+            "go" => self.go(argument),
+            "analyze" => {
+                no_argument()?;
+                let analysis =
+                    analyze::analyze(&self.config, &self.game).map_err(|e| e.to_string())?;
+                let lines: Vec<String> = analysis
+                    .iter()
+                    .map(|a| match a.searched {
+                        true => {
+                            let pv = record::moves_to_string(&a.pv.moves);
+                            format!("{:>3}. {:<4} {:+8.2}  {}", a.rank, a.m, a.score, pv)
+                        }
+                        false => format!("  -. {:<4} {:>8}  not searched", a.m, "-"),
+                    })
+                    .collect();
+                Ok(lines.join("\n"))
+            }
+            "perft" => {
+                let depth: u8 = match argument.parse() {
+                    Ok(depth) if depth > 0 => depth,
+                    _ => return Err("perft needs a depth of 1 or more, e.g. perft 4.".to_string()),
+                };
+                let start = Instant::now();
+                let divided = perft::divide(&self.game, depth);
+                let mut lines: Vec<String> = divided
+                    .iter()
+                    .map(|(m, count)| format!("{:<4} {}", m, count))
+                    .collect();
+                let total: u64 = divided.iter().map(|(_, count)| count).sum();
+                lines.push(format!("Nodes: {} ({:.3?})", total, start.elapsed()));
+                Ok(lines.join("\n"))
+            }
+            "hash" => {
+                no_argument()?;
+                Ok(format!("{:016x}", zobrist::zobrist(&self.game)))
+            }
+            "notation" => {
+                no_argument()?;
+                Ok(notation::to_notation(&self.game))
+            }
+            "history" => {
+                no_argument()?;
+                let start = self.start.as_deref().unwrap_or("the starting position");
+                Ok(format!(
+                    "From {}: {}",
+                    start,
+                    record::moves_to_string(&self.moves)
+                ))
+            }
+            "help" => Ok(HELP.to_string()),
+            "" => Ok("".to_string()),
+            _ => Err(format!(
+                "Unknown command: {}. Enter `help` for the commands.",
+                command
+            )),
+        }
+    }
+
+    fn position(&mut self, position: &str) -> Result<String, String> {
+        let game = notation::from_notation(position).map_err(|e| e.to_string())?;
+        self.start = Some(position.to_string());
+        self.moves.clear();
+        self.game = game;
+        Ok("Position set.".to_string())
+    }
+
+    /// Plays moves, either all of them or none.
+    fn play(&mut self, moves: &str) -> Result<String, String> {
+        let mut game = self.game.clone();
+        let mut played = vec![];
+        for input in moves.split(|c: char| c.is_whitespace() || c == ';') {
+            if input.is_empty() {
+                continue;
+            }
+            if game.is_over() {
+                return Err(format!("The game is over before {}.", input));
+            }
+            let m = play::parse_move(&game, input)?;
+            game.take_turn(&m);
+            played.push(m);
+        }
+        if played.is_empty() {
+            return Err("move needs a move, e.g. move 6L.".to_string());
+        }
+        self.game = game;
+        self.moves.extend(&played);
+        Ok(format!("Played {}.", record::moves_to_string(&played)))
+    }
+
+    fn go(&mut self, argument: &str) -> Result<String, String> {
+        let mut config = self.config.clone();
+        let words: Vec<&str> = argument.split_whitespace().collect();
+        match words[..] {
+            [] => {}
+            ["depth", depth] => {
+                config.max_depth = depth
+                    .parse()
+                    .map_err(|_| format!("Invalid depth: {}", depth))?;
+                config.max_time_ms = None;
+            }
+            _ => return Err(format!("Usage: go [depth N], not go {}", argument)),
+        }
+        let pvline =
+            negamax::search(config, self.game.clone(), false).map_err(|e| e.to_string())?;
+        let best = pvline.moves.first().ok_or("There is no legal move.")?;
+        Ok(format!(
+            "Best move: {} ({:+.2})\nPV: {}",
+            best,
+            pvline.value,
+            record::moves_to_string(&pvline.moves)
+        ))
+    }
+
+    /// Returns the position after playing moves from the starting position.
+    fn replay(&self, moves: &[Move]) -> Result<Game, String> {
+        let mut game = match &self.start {
+            Some(position) => notation::from_notation(position).map_err(|e| e.to_string())?,
+            None => Game::new(),
+        };
+        for m in moves {
+            game.take_turn(m);
+        }
+        Ok(game)
+    }
+}
+
+/// Runs the shell until `quit` or the end of the input.
+///
+/// # Arguments
+///
+/// * `config` - The search configuration of `go` and `analyze`.
+pub fn run(config: SearchConfig) -> ShellResult<()> {
+    let mut shell = Shell::new(config);
+    let mut editor = DefaultEditor::new()?;
+    loop {
+        let line = match editor.readline("kikande> ") {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) | Err(ReadlineError::Eof) => break,
+            Err(e) => return Err(e.into()),
+        };
+        if matches!(line.trim(), "quit" | "exit") {
+            break;
+        }
+        if !line.trim().is_empty() {
+            editor.add_history_entry(line.as_str())?;
+        }
+        match shell.execute(&line) {
+            Ok(output) if output.is_empty() => {}
+            Ok(output) => println!("{}", output),
+            Err(message) => println!("Error: {}", message),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_shell_commands() {
+        let mut shell = Shell::new(SearchConfig::new(2, 1, None));
+        assert_eq!(
+            shell.execute("move 6L 5R; 3L"),
+            Ok("Played 6L 5R; 3L.".to_string())
+        );
+        assert_eq!(
+            shell.execute("history"),
+            Ok("From the starting position: 6L 5R; 3L".to_string())
+        );
+        let notation = shell.execute("notation").expect("No notation");
+        assert_eq!(shell.execute("undo"), Ok("Took back 3L.".to_string()));
+        // Illegal moves leave the position unchanged.
+        assert!(shell.execute("move 3L 1L").is_err());
+        assert_eq!(
+            shell.execute("history").map(|h| h.ends_with("6L 5R")),
+            Ok(true)
+        );
+
+        assert_eq!(
+            shell.execute(&format!("position {}", notation)),
+            Ok("Position set.".to_string())
+        );
+        assert_eq!(shell.execute("notation"), Ok(notation));
+        assert!(shell
+            .execute("go depth 2")
+            .is_ok_and(|out| out.starts_with("Best move: ")));
+        assert!(shell.execute("perft x").is_err());
+        assert!(shell.execute("castle").is_err());
+        // Non-ASCII moves are rejected instead of slicing inside a character.
+        for input in ["move é", "move Aé", "move 6é", "move ⁶L"] {
+            assert!(shell.execute(input).is_err());
+        }
+
+        shell.execute("new").expect("New game failed");
+        let perft = shell.execute("perft 2").expect("Perft failed");
+        assert!(perft.contains("Nodes: 14 "));
+    }
+}
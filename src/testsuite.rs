@@ -0,0 +1,186 @@
+//! Test suites of positions with expected best moves.
+//!
+//! Every line holds a position in notation followed by operations separated
+//! by semicolons, in the style of EPD:
+//!
+//! ```text
+//! <position> bm 5L 5R; am 3L; id "namua 1";
+//! ```
+//!
+//! `bm` lists the best moves, `am` the moves to avoid and `id` names the
+//! test. Other operations, such as the `pv` and `gain` of puzzles, are
+//! ignored. Lines starting with `#` are comments.
+
+use crate::bao::game::Game;
+use crate::bao::move_factory::MoveFactory;
+use crate::bao::moves::Move;
+use crate::bao::notation;
+use crate::bao::pv::PVLine;
+use crate::config::SearchConfig;
+use crate::error::{SearchResult, TestSuiteError, TestSuiteResult};
+use crate::search::negamax;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+/// A test position with its expected moves.
+#[derive(Clone, Debug)]
+pub struct TestPosition {
+    pub game: Game,
+    pub id: String,
+    /// The search has to find one of these moves, if there are any.
+    pub best_moves: Vec<Move>,
+    /// The search must not find one of these moves.
+    pub avoid_moves: Vec<Move>,
+}
+
+impl TestPosition {
+    /// Returns whether a move solves the test.
+    pub fn is_solved_by(&self, m: &Move) -> bool {
+        (self.best_moves.is_empty() || self.best_moves.contains(m)) && !self.avoid_moves.contains(m)
+    }
+}
+
+/// The outcome of a test.
+#[derive(Clone, Debug)]
+pub struct TestOutcome {
+    pub id: String,
+    pub passed: bool,
+    /// The principal variation of the search.
+    pub pvline: PVLine,
+    /// The time after which the search found a solving move and kept it.
+    pub solve_time: Option<Duration>,
+    pub time: Duration,
+    pub nodes: u64,
+}
+
+/// Parses a test suite.
+pub fn parse_test_suite(s: &str) -> TestSuiteResult<Vec<TestPosition>> {
+    let mut positions = vec![];
+    for (i, line) in s.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let invalid = || TestSuiteError::InvalidLine(i + 1, line.to_string());
+
+        // The position is made of the first three fields.
+        let fields: Vec<&str> = line.splitn(4, char::is_whitespace).collect();
+        if fields.len() < 3 {
+            return Err(invalid());
+        }
+        let game = notation::from_notation(&fields[..3].join(" "))?;
+        let factory = MoveFactory::new(&game);
+        let mut position = TestPosition {
+            game: game.clone(),
+            id: format!("line {}", i + 1),
+            best_moves: vec![],
+            avoid_moves: vec![],
+        };
+        for operation in fields.get(3).unwrap_or(&"").split(';') {
+            let operation = operation.trim();
+            let (opcode, operands) = operation.split_once(' ').unwrap_or((operation, ""));
+            let parse_moves = |operands: &str| {
+                operands
+                    .split_whitespace()
+                    .map(|m| factory.parse_move(m))
+                    .collect::<Result<Vec<Move>, _>>()
+            };
+            match opcode {
+                "bm" => position.best_moves = parse_moves(operands)?,
+                "am" => position.avoid_moves = parse_moves(operands)?,
+                "id" => position.id = operands.trim().trim_matches('"').to_string(),
+                _ => {}
+            }
+        }
+        if position.best_moves.is_empty() && position.avoid_moves.is_empty() {
+            return Err(invalid());
+        }
+        positions.push(position);
+    }
+    Ok(positions)
+}
+
+/// Loads a test suite from a file.
+pub fn load<P: AsRef<Path>>(path: P) -> TestSuiteResult<Vec<TestPosition>> {
+    parse_test_suite(&fs::read_to_string(path)?)
+}
+
+/// Searches a test position with the depth, time or node limit of the
+/// search configuration.
+pub fn run_test(position: &TestPosition, config: &SearchConfig) -> SearchResult<TestOutcome> {
+    let mut solve_time = None;
+    let mut time = Duration::ZERO;
+    let mut nodes = 0;
+    let pvline = negamax::search_with_observer(config.clone(), position.game.clone(), |info| {
+        let solved = info.pvlines[0]
+            .moves
+            .first()
+            .is_some_and(|m| position.is_solved_by(m));
+        solve_time = match solved {
+            true => solve_time.or(Some(info.elapsed)),
+            false => None,
+        };
+        time = info.elapsed;
+        nodes = info.nodes;
+    })?;
+    let passed = pvline
+        .moves
+        .first()
+        .is_some_and(|m| position.is_solved_by(m));
+    Ok(TestOutcome {
+        id: position.id.clone(),
+        passed,
+        pvline,
+        solve_time: solve_time.filter(|_| passed),
+        time,
+        nodes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const SUITE: &str = "
+# Mtaji tactics.
+0,3,1,0,0,0,0,0,0,0,0,0,0,0,0,0/0,0,0,0,0,3,0,0,0,0,0,0,0,0,0,0 0/0 00 bm A2R; id \"relay\";
+0,3,1,0,0,0,0,0,0,0,0,0,0,0,0,0/0,0,0,0,0,3,0,0,0,0,0,0,0,0,0,0 0/0 00 am A2R; pv \"A2R\";
+";
+
+    #[test]
+    fn test_parse_test_suite() -> TestSuiteResult<()> {
+        let positions = parse_test_suite(SUITE)?;
+        assert_eq!(positions.len(), 2);
+        assert_eq!(positions[0].id, "relay");
+        assert_eq!(positions[0].best_moves, vec![Move::mtaji_relay_right(1)]);
+        assert_eq!(positions[1].id, "line 4");
+        assert_eq!(positions[1].avoid_moves, vec![Move::mtaji_relay_right(1)]);
+
+        assert!(matches!(
+            parse_test_suite("0,0/0,0 1/1 11 bm 6L;"),
+            Err(TestSuiteError::InvalidPosition(_))
+        ));
+        let start = "0,0,0,0,6,2,2,0,0,0,0,0,0,0,0,0/0,0,0,0,6,2,2,0,0,0,0,0,0,0,0,0 22/22 11";
+        assert!(matches!(
+            parse_test_suite(&format!("{} id \"no moves\";", start)),
+            Err(TestSuiteError::InvalidLine(1, _))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_test() -> TestSuiteResult<()> {
+        let positions = parse_test_suite(SUITE)?;
+        let config = SearchConfig::new(4, 1, None);
+        let outcome = run_test(&positions[0], &config)?;
+        assert!(outcome.passed);
+        assert!(outcome.solve_time.is_some());
+        assert!(outcome.nodes > 0);
+        let outcome = run_test(&positions[1], &config)?;
+        assert!(!outcome.passed);
+        assert!(outcome.solve_time.is_none());
+        Ok(())
+    }
+}